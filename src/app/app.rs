@@ -1,6 +1,24 @@
-use crate::{krypt, models::*, pw_gen::*, vault};
+use crate::{
+  clipboard::{ClearDeadline, SecretClipboard},
+  domain,
+  error::VaultError,
+  krypt::{self, KdfCalibration, KeyFile, SessionKey},
+  lock::{self, LockError, LockOwner, VaultLock},
+  merge::{self, MergeOutcome},
+  models::*,
+  pw_gen::*,
+  settings::{self, Settings},
+  vault,
+};
 use eframe::egui;
+use std::{
+  collections::HashMap,
+  sync::mpsc,
+  time::{Duration, Instant},
+};
+use zeroize::{Zeroize, Zeroizing};
 use egui_toast::{Toast, ToastKind, ToastOptions, ToastStyle, Toasts};
+use uuid::Uuid;
 
 /// State of the application, from selection, opening vaults, to help screen
 #[derive(Default)]
@@ -12,6 +30,8 @@ pub enum AppState {
   Unlocked {
     feature_state: FeatureState,
   },
+  /// Confirm the master password of a backup before restoring it
+  RestoreBackup,
   Help,
 }
 
@@ -23,6 +43,12 @@ pub enum FeatureState {
   },
   /// Edit an existing entry
   EditEntry,
+  /// Re-encrypt the vault under a new master password
+  ChangeMasterPassword,
+  /// Rename the vault and edit its other metadata
+  VaultSettings,
+  /// Add or revoke the passwords that can open the vault
+  KeySlots,
 }
 impl Default for FeatureState {
   fn default() -> Self {
@@ -32,6 +58,27 @@ impl Default for FeatureState {
   }
 }
 
+/// External changes to the vault file waiting for conflicts to be resolved
+pub struct PendingMerge {
+  /// Merged entries and the conflicts left to resolve
+  pub outcome: MergeOutcome,
+  /// Vault as found on disk, becomes the new merge base
+  theirs: PasswordVault,
+  /// Fingerprint of the file `theirs` was read from
+  fingerprint: Option<vault::FileFingerprint>,
+}
+
+/// Toast kind showing when a copied password is cleared from the clipboard
+const CLIPBOARD_TOAST: u32 = 0;
+
+/// Minimum zxcvbn score (0-4) accepted for a new master password
+pub const MIN_MASTER_PASSWORD_SCORE: u8 = 3;
+
+/// Whether `password` is strong enough to open a vault, as a master or key slot password
+fn is_strong_master_password(password: &str) -> bool {
+  PasswordGenerator::get_password_score(password) >= MIN_MASTER_PASSWORD_SCORE
+}
+
 /// App state variables
 #[derive(Default)]
 pub struct PixelVaultApp {
@@ -39,21 +86,83 @@ pub struct PixelVaultApp {
   state: AppState,
   /// List of available vaults in filepaths
   available_vaults: Vec<String>,
+  /// Unencrypted metadata of the available vaults that have it
+  vault_metadata: HashMap<String, VaultMetadata>,
   /// Actual vault selected out of available (filepath)
   selected_vault: Option<String>,
+  /// Vault whose backups are listed in `AppState::SelectVault`
+  pub(crate) backups_shown_for: Option<String>,
+  /// Vault waiting for delete confirmation in `AppState::SelectVault`
+  pub(crate) delete_vault_confirmation: Option<String>,
+  /// Backup picked to be restored over `selected_vault`
+  pub(crate) restore_backup: Option<vault::BackupInfo>,
+  /// Vault directories, registered vault files, and other persisted settings
+  pub(crate) settings: Settings,
+  /// Index into `settings.vault_dirs` where a new vault is created
+  pub(crate) new_vault_dir: usize,
+  /// Directory path typed in to be watched for vaults
+  pub(crate) vault_dir_input: String,
+  /// Vault file path typed in to be registered
+  pub(crate) vault_file_input: String,
+  /// Exclusive lock on `selected_vault`, released on drop
+  vault_lock: Option<VaultLock>,
+  /// Vault was opened without its lock, so it is never saved
+  pub(crate) read_only: bool,
+  /// Vault path and owner of a lock that stopped the vault from opening
+  pub(crate) lock_conflict: Option<(String, LockOwner)>,
 
-  /// Master password for the current vault
-  pub(crate) master_password: String,
+  /// Why the last unlock attempt failed, shown on the old vault screen
+  pub(crate) unlock_error: Option<VaultError>,
+  /// Master password for the current vault, wiped once the vault is unlocked
+  pub(crate) master_password: Zeroizing<String>,
   /// Confirmation compared to master_password during vault creation
-  pub(crate) master_password_confirm: String,
+  pub(crate) master_password_confirm: Zeroizing<String>,
+  /// Key file picked for creating, unlocking or restoring a vault, empty for none
+  pub(crate) key_file_path: String,
+  /// Make a recovery key along with a new vault
+  pub(crate) recovery_key_opt_in: bool,
+  /// Unlock with `recovery_key_input` instead of the master password
+  pub(crate) use_recovery_key: bool,
+  /// Recovery key typed in on the old vault screen
+  pub(crate) recovery_key_input: Zeroizing<String>,
+  /// Recovery key just made, shown once until the user has written it down
+  pub(crate) new_recovery_key: Option<Zeroizing<String>>,
+  /// User confirmed `new_recovery_key` was printed or written down
+  pub(crate) recovery_key_written_down: bool,
+  /// Vault was unlocked with its recovery key, and needs a new master password
+  pub(crate) must_reset_password: bool,
+
+  // Change master password form fields
+  pub(crate) current_password_check: Zeroizing<String>,
+  pub(crate) new_master_password: Zeroizing<String>,
+  pub(crate) new_master_password_confirm: Zeroizing<String>,
 
   /// Vault name used when making a new vault
   pub(crate) new_vault_name: String,
+  // Key slot form fields
+  pub(crate) new_slot_label: String,
+  pub(crate) new_slot_password: Zeroizing<String>,
+  pub(crate) new_slot_password_confirm: Zeroizing<String>,
+
+  // Vault settings form fields
+  pub(crate) vault_name_input: String,
+  pub(crate) vault_description_input: String,
+  pub(crate) encrypt_metadata_input: bool,
+  pub(crate) cipher_input: CipherKind,
+
+  /// Cipher picked for a new vault
+  pub(crate) new_vault_cipher: CipherKind,
+  /// KDF costs picked for this machine, used when making a new vault
+  pub(crate) new_vault_kdf: Option<KdfCalibration>,
+  /// Receives the result of a running KDF calibration
+  kdf_calibration: Option<mpsc::Receiver<Result<KdfCalibration, VaultError>>>,
 
   // Entry form fields
   pub(crate) new_service: String,
   pub(crate) new_username: String,
-  pub(crate) new_password: String,
+  pub(crate) new_password: Zeroizing<String>,
+  /// Websites of the new entry, one per line
+  pub(crate) new_urls: String,
 
   /// Search query for services / usernames when `AppState::Unlocked`
   pub(crate) search_query: String,
@@ -65,17 +174,33 @@ pub struct PixelVaultApp {
   pub(crate) vault: Option<PasswordVault>,
   /// Encrypted vault stored in file
   pub(crate) encrypted_vault: Option<EncryptedVault>,
+  /// Key derived from the master password while the vault is unlocked
+  session_key: Option<SessionKey>,
+  /// Vault as last loaded from or saved to disk, the base for merging
+  base_vault: Option<PasswordVault>,
+  /// Fingerprint of the vault file when `base_vault` was read or written
+  loaded_fingerprint: Option<vault::FileFingerprint>,
+  /// Conflicts found while merging changes made to the file elsewhere
+  pub(crate) pending_merge: Option<PendingMerge>,
 
-  /// Index containing the password position to delete
-  pub(crate) delete_confirmation_index: Option<usize>,
-  /// Index for which password to edit
-  pub(crate) edit_index: Option<usize>,
+  /// ID of the entry waiting for delete confirmation
+  pub(crate) delete_confirmation_id: Option<Uuid>,
+  /// Copy of the entry being edited, written back on save
+  pub(crate) edit_draft: Option<PasswordEntry>,
+  /// Websites of the edit draft as typed, one per line
+  pub(crate) edit_urls: String,
 
   // Display
-  /// Index storing shown password index so only one is shown at a time
-  pub(crate) show_password_index: Option<usize>,
+  /// ID of the entry whose password is shown, so only one is shown at a time
+  pub(crate) show_password_id: Option<Uuid>,
+  /// Last keyboard or mouse input while unlocked, for auto-lock
+  last_activity: Option<Instant>,
+  /// Since when the window has been minimized or unfocused while unlocked
+  background_since: Option<Instant>,
   /// Replaces error_message, to show notifications and errors
   toasts: Toasts,
+  /// Clears copied passwords from the system clipboard
+  clipboard: SecretClipboard,
 }
 
 impl PixelVaultApp {
@@ -89,16 +214,31 @@ impl PixelVaultApp {
     visuals.widgets.noninteractive.corner_radius = 8.0.into();
     cc.egui_ctx.set_visuals(visuals);
 
-    std::fs::create_dir_all("vaults").ok();
+    let (settings, settings_error) = match settings::load() {
+      Ok(settings) => (settings, None),
+      Err(e) => (Settings::default(), Some(e)),
+    };
+    std::fs::create_dir_all(settings::default_vault_dir()).ok();
 
-    Self {
+    let clipboard = SecretClipboard::default();
+    let clear_deadline = clipboard.deadline();
+    let mut app = Self {
       state: AppState::SelectVault,
-      available_vaults: vault::list_vaults(),
+      settings,
       toasts: Toasts::new()
         .anchor(egui::Align2::RIGHT_BOTTOM, (-10.0, -10.0))
-        .direction(egui::Direction::BottomUp),
+        .direction(egui::Direction::BottomUp)
+        .custom_contents(CLIPBOARD_TOAST, move |ui, toast| {
+          clipboard_toast_contents(ui, toast, &clear_deadline)
+        }),
+      clipboard,
       ..Default::default()
+    };
+    app.reload_available_vaults();
+    if let Some(e) = settings_error {
+      app.show_error(format!("Settings failed to load, using defaults: {}", e));
     }
+    app
   }
   
   /// Uses `egui_toast` to show error notification
@@ -153,21 +293,339 @@ impl PixelVaultApp {
     });
   }
 
+  /// Copies a password, cleared from the clipboard after
+  /// `settings.clipboard_clear_secs` if it is still there.
+  /// `what` names the secret in the toast, e.g. "Password".
+  pub fn copy_secret(&mut self, secret: &str, what: &str) {
+    let secs = self.settings.clipboard_clear_secs;
+    let clear_after = (secs > 0).then(|| Duration::from_secs(secs));
+    match self.clipboard.copy(secret, clear_after) {
+      Ok(()) if clear_after.is_some() => {
+        self.toasts.add(Toast {
+          style: ToastStyle::default(),
+          text: format!("{} copied!", what).into(),
+          kind: ToastKind::Custom(CLIPBOARD_TOAST),
+          options: ToastOptions::default()
+            .duration(clear_after)
+            .show_progress(true)
+            .show_icon(true),
+        });
+      }
+      Ok(()) => self.show_info(format!("{} copied!", what)),
+      Err(e) => self.show_error(format!("Failed to copy to the clipboard: {}", e)),
+    }
+  }
+
   /// Encrypts and saves current vault state.
   /// 
+  /// Reuses the session key, so only a fresh nonce is generated and the
+  /// KDF doesn't run again. Changes made to the file elsewhere are merged first.
+  /// 
   /// # Errors
   /// Returns an error if:
-  /// - Vault / vault path doesn't exist
+  /// - Vault is open read-only
+  /// - Vault / vault path / session key doesn't exist
+  /// - The file changed elsewhere and can't be merged without the user
   /// - Encryption fails
   /// - Filesystem save fails
   pub fn save_vault(&mut self) -> Result<(), String> {
-    let plaintext = self.vault.as_ref().ok_or("Vault locked")?;
+    if self.read_only {
+      return Err("Vault is open read-only".into());
+    }
+    self.merge_external_changes()?;
+    let plaintext = self.vault.as_mut().ok_or("Vault locked")?;
+    plaintext.touch();
+    let path = self.selected_vault.clone().ok_or("No vault path")?;
+    let key = self.session_key.as_ref().ok_or("Vault locked")?;
+
+    let encrypted = krypt::encrypt_vault(plaintext, key)?;
+
+    let fingerprint = self.write_vault_file(&path, &encrypted)?;
+    self.encrypted_vault = Some(encrypted);
+    self.record_saved_state(Some(fingerprint));
+    Ok(())
+  }
+
+  /// Saves `encrypted` to `path`, warning if its timestamped backups
+  /// couldn't be updated. The vault itself is saved in that case.
+  /// 
+  /// # Errors
+  /// Returns an error if the vault file wasn't replaced
+  fn write_vault_file(
+    &mut self,
+    path: &str,
+    encrypted: &EncryptedVault,
+  ) -> Result<vault::FileFingerprint, String> {
+    let saved = vault::save(path, encrypted, &self.settings.backup_policy)?;
+    if let Some(e) = saved.backup_error {
+      self.show_warning(format!("Vault saved, but its backups couldn't be updated: {}", e));
+    }
+    Ok(saved.fingerprint)
+  }
+
+  /// Remembers the current vault as the merge base, along with the
+  /// fingerprint of the file bytes it was read from or written as
+  fn record_saved_state(&mut self, fingerprint: Option<vault::FileFingerprint>) {
+    self.base_vault = self.vault.clone();
+    self.loaded_fingerprint = fingerprint;
+  }
+
+  /// Merges changes made to the vault file since it was loaded or last saved.
+  /// 
+  /// Entries changed on one side merge automatically. If both sides changed
+  /// the same entry, the merge is kept in `pending_merge` for the user.
+  /// A file that was replaced with something unreadable, or whose encrypted
+  /// data was damaged, is copied aside before the save overwrites it.
+  /// 
+  /// # Errors
+  /// Returns an error if:
+  /// - A merge is already waiting on the user, or this one needs them
+  /// - The file was re-encrypted with a different master password
+  /// - The file can't be read, or an unreadable one can't be copied aside
+  fn merge_external_changes(&mut self) -> Result<(), String> {
+    if self.pending_merge.is_some() {
+      return Err("Resolve the merge conflicts first".into());
+    }
     let path = self.selected_vault.as_ref().ok_or("No vault path")?;
+    let (data, fingerprint) = match vault::read_with_fingerprint(path) {
+      Ok((data, fingerprint)) => (data, Some(fingerprint)),
+      Err(VaultError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+        if self.loaded_fingerprint.is_some() {
+          self.show_warning("Vault file was removed elsewhere, saving it again");
+        }
+        return Ok(());
+      }
+      Err(e) => return Err(e.to_string()),
+    };
+    if fingerprint == self.loaded_fingerprint {
+      return Ok(());
+    }
+
+    let key = self.session_key.as_ref().ok_or("Vault locked")?;
+    let decrypted = vault::decode(&data)
+      .and_then(|on_disk| Ok((krypt::decrypt_vault(&on_disk, key)?, on_disk)));
+    let (mut theirs, on_disk) = match decrypted {
+      Ok(decrypted) => decrypted,
+      Err(VaultError::Malformed(_) | VaultError::DataTampered) => {
+        let copy = vault::preserve_unreadable(path)
+          .map_err(|e| format!("Vault file was replaced with an unreadable file: {}", e))?;
+        self.show_warning(format!(
+          "Vault file was replaced with an unreadable file, kept a copy at {}",
+          copy
+        ));
+        return Ok(());
+      }
+      Err(VaultError::AuthenticationFailed) => {
+        return Err(
+          "Vault was changed elsewhere with a different master password, lock and reopen it".into()
+        );
+      }
+      Err(e) => return Err(e.to_string()),
+    };
+    // An older build may have saved entries without IDs
+    theirs.assign_entry_ids(self.base_vault.as_ref());
+    // Key slots may have been added or removed elsewhere
+    if let Some(key) = self.session_key.as_mut() {
+      key.adopt_slots(&on_disk);
+    }
+    let base = self.base_vault.as_ref().ok_or("Vault locked")?;
+    let mine = self.vault.as_ref().ok_or("Vault locked")?;
+
+    let pending = PendingMerge {
+      outcome: merge::three_way(base, mine, &theirs),
+      theirs,
+      fingerprint,
+    };
+    let conflicts = pending.outcome.conflicts.len();
+    if conflicts == 0 {
+      self.apply_merge(pending)?;
+      self.show_info("Merged changes made to the vault elsewhere");
+      Ok(())
+    } else {
+      self.pending_merge = Some(pending);
+      Err(format!(
+        "Vault was changed elsewhere, {} conflicting entries need resolving",
+        conflicts
+      ))
+    }
+  }
+
+  /// Replaces the entries with a fully resolved merge
+  fn apply_merge(&mut self, pending: PendingMerge) -> Result<(), String> {
+    let entries = pending.outcome.resolved_entries().ok_or("Unresolved merge conflicts")?;
+    let vault = self.vault.as_mut().ok_or("Vault locked")?;
+    vault.entries = entries;
+    self.base_vault = Some(pending.theirs);
+    self.loaded_fingerprint = pending.fingerprint;
+    Ok(())
+  }
+
+  /// Applies the user's picks for `pending_merge` and saves the vault
+  /// 
+  /// # Errors
+  /// Returns an error if a conflict has no pick yet, or saving fails
+  pub fn resolve_merge_conflicts(&mut self) -> Result<(), String> {
+    let pending = self.pending_merge.take().ok_or("No merge in progress")?;
+    if pending.outcome.resolved_entries().is_none() {
+      self.pending_merge = Some(pending);
+      return Err("Pick a side for every conflict".into());
+    }
+    self.apply_merge(pending)?;
+    self.save_vault()
+  }
 
-    let encrypted = krypt::encrypt_vault(plaintext, &self.master_password)?;
+  /// Re-wraps the vault data key for `new_master_password` after verifying
+  /// `current_password_check` against the key slot the vault was unlocked with.
+  /// 
+  /// Only that slot gets a new salt, other key slots keep working. The new
+  /// file is written atomically, so a crash leaves either the old or the new vault readable.
+  /// 
+  /// # Errors
+  /// Returns an error if:
+  /// - Vault is open read-only
+  /// - The file changed elsewhere and can't be merged without the user
+  /// - The current password is wrong (not checked after unlocking with a recovery key)
+  /// - The new password is empty, unconfirmed, unchanged, or too weak
+  /// - Encryption or the filesystem save fails
+  pub fn change_master_password(&mut self) -> Result<(), String> {
+    if self.read_only {
+      return Err("Vault is open read-only".into());
+    }
+    self.merge_external_changes()?;
+    let plaintext = self.vault.as_ref().ok_or("Vault locked")?;
+    let path = self.selected_vault.clone().ok_or("No vault path")?;
+    let key = self.session_key.as_ref().ok_or("Vault locked")?;
+
+    let recovering = self.must_reset_password;
+    if !recovering && !key.verify_password(&self.current_password_check) {
+      return Err("Current master password is incorrect".into());
+    }
+    if self.new_master_password.is_empty() {
+      return Err("New master password cannot be empty".into());
+    } else if self.new_master_password != self.new_master_password_confirm {
+      return Err("New passwords do not match".into());
+    } else if !recovering && self.new_master_password == self.current_password_check {
+      return Err("New master password must be different".into());
+    } else if !is_strong_master_password(&self.new_master_password) {
+      return Err("New master password is too weak".into());
+    }
+
+    let mut new_key = key.clone();
+    if recovering {
+      new_key.reset_password(&self.new_master_password)?;
+    } else {
+      new_key.change_password(&self.new_master_password, &key.kdf())?;
+    }
+    let encrypted = krypt::encrypt_vault(plaintext, &new_key)?;
+    let fingerprint = self.write_vault_file(&path, &encrypted)?;
 
-    vault::save(path, &encrypted)?;
     self.encrypted_vault = Some(encrypted);
+    self.session_key = Some(new_key);
+    self.record_saved_state(Some(fingerprint));
+    self.must_reset_password = false;
+    self.current_password_check.zeroize();
+    self.new_master_password.zeroize();
+    self.new_master_password_confirm.zeroize();
+    Ok(())
+  }
+
+  /// Opens `FeatureState::KeySlots` with an empty form
+  pub fn go_to_key_slots(&mut self) {
+    self.new_slot_label.clear();
+    self.new_slot_password.zeroize();
+    self.new_slot_password_confirm.zeroize();
+    self.key_file_path.clear();
+    if let AppState::Unlocked { feature_state } = &mut self.state {
+      *feature_state = FeatureState::KeySlots;
+    }
+  }
+
+  /// Key slots of the unlocked vault
+  pub fn get_key_slots(&self) -> Option<&[KeySlot]> {
+    self.session_key.as_ref().map(|key| key.slots())
+  }
+
+  /// Whether `slot` is the key slot the vault was unlocked with
+  pub fn is_unlocked_slot(&self, slot: &KeySlot) -> bool {
+    self.session_key.as_ref().is_some_and(|key| key.is_unlocked_slot(slot))
+  }
+
+  /// Adds a key slot for `new_slot_password`, and the key file in
+  /// `key_file_path` if one is picked, then saves the vault.
+  /// 
+  /// # Errors
+  /// Returns an error if:
+  /// - The label or password is empty, the password unconfirmed or too weak
+  /// - The key file can't be read
+  /// - Key derivation or saving fails
+  pub fn add_key_slot(&mut self) -> Result<(), String> {
+    let label = self.new_slot_label.trim().to_string();
+    if label.is_empty() {
+      return Err("Key slot label cannot be empty".into());
+    } else if self.new_slot_password.is_empty() {
+      return Err("Password cannot be empty".into());
+    } else if self.new_slot_password != self.new_slot_password_confirm {
+      return Err("Passwords do not match".into());
+    } else if !is_strong_master_password(&self.new_slot_password) {
+      return Err("Password is too weak".into());
+    }
+    let key_file = self.selected_key_file()?;
+    let password = self.new_slot_password.clone();
+    self.update_session_key(|key| {
+      let kdf = key.kdf();
+      key.add_slot(&label, &password, &kdf, key_file.as_ref())
+    })?;
+    self.new_slot_label.clear();
+    self.new_slot_password.zeroize();
+    self.new_slot_password_confirm.zeroize();
+    self.key_file_path.clear();
+    Ok(())
+  }
+
+  /// Removes a key slot, so its password no longer opens the vault, and saves.
+  /// 
+  /// # Errors
+  /// Returns an error for the slot in use, or if saving fails
+  pub fn remove_key_slot(&mut self, index: usize) -> Result<(), String> {
+    self.update_session_key(|key| key.remove_slot(index))
+  }
+
+  /// Makes a new recovery key for the unlocked vault, replacing any earlier
+  /// one, saves, and shows it once.
+  /// 
+  /// # Errors
+  /// Returns an error if key derivation or saving fails
+  pub fn create_recovery_key(&mut self) -> Result<(), String> {
+    let recovery_key = krypt::generate_recovery_key();
+    self.update_session_key(|key| key.set_recovery_key(&recovery_key))?;
+    self.new_recovery_key = Some(recovery_key);
+    Ok(())
+  }
+
+  /// Whether the unlocked vault has a recovery key
+  pub fn has_recovery_key(&self) -> bool {
+    self.session_key.as_ref().is_some_and(|key| key.has_recovery_key())
+  }
+
+  /// Edits the session key (its key slots or cipher) and saves, keeping
+  /// the old session key if anything fails.
+  fn update_session_key(
+    &mut self,
+    update: impl FnOnce(&mut SessionKey) -> Result<(), VaultError>,
+  ) -> Result<(), String> {
+    if self.read_only {
+      return Err("Vault is open read-only".into());
+    }
+    // Start from the slots on disk, in case they were changed elsewhere
+    self.merge_external_changes()?;
+    let old_key = self.session_key.clone().ok_or("Vault locked")?;
+    let mut key = old_key.clone();
+    update(&mut key)?;
+    self.session_key = Some(key);
+    if let Err(e) = self.save_vault() {
+      self.session_key = Some(old_key);
+      return Err(e);
+    }
     Ok(())
   }
 
@@ -176,17 +634,18 @@ impl PixelVaultApp {
     self.vault.as_ref()
   }
 
-  /// Try to create a vault
+  /// Try to create a vault, in a file named after the vault
   /// 
   /// # Errors
   /// Shows user an error if
-  /// - Vault name / master password is empty
+  /// - Vault name is invalid, see [`vault::validate_name`]
+  /// - Master password is empty
   /// - Master password confirm is different
-  /// - A vault at the path already exists
+  /// - The key file can't be read
   /// - Initializing a vault fails
   pub fn attempt_create_vault(&mut self) {
-    if self.new_vault_name.trim().is_empty() {
-      self.show_error("Vault name cannot be empty");
+    if let Err(e) = vault::validate_name(&self.new_vault_name) {
+      self.show_error(e.to_string());
       return;
     } else if self.master_password.is_empty() {
       self.show_error("Vault master password cannot be empty");
@@ -194,62 +653,264 @@ impl PixelVaultApp {
     } else if self.master_password != self.master_password_confirm {
       self.show_error("Passwords do not match");
       return;
+    } else if self.is_calibrating_kdf() {
+      self.show_error("Still calibrating key derivation, try again in a moment");
+      return;
     }
-    // Fallback to empty if no new vault name (somehow ig)
-    let _fallback_vault_name = chrono::Utc::now().timestamp().to_string();
-    let path = format!(
-      "vaults/{}.json",
-      if !self.new_vault_name.is_empty() {
-        self.new_vault_name.trim()
-      } else {
-        &_fallback_vault_name
+    let dir = self
+      .settings
+      .vault_dirs
+      .get(self.new_vault_dir)
+      .map(std::path::PathBuf::from)
+      .unwrap_or_else(settings::default_vault_dir);
+    let path = match vault::new_vault_path(&dir, &self.new_vault_name) {
+      Ok(path) => path.to_string_lossy().to_string(),
+      Err(e) => {
+        self.show_error(e.to_string());
+        return;
       }
-    );
-    if self.get_available_vaults().contains(&path) {
-      self.show_error("Vault already exists");
+    };
+    // Stay on this screen if the key file, lock or save fails
+    if let Err(e) = self.create_new_vault(&path) {
+      self.show_error(e);
       return;
     }
+    self.selected_vault = Some(path);
     self.state = AppState::Unlocked {
       feature_state: FeatureState::default()
     };
-    self.selected_vault = Some(path.clone());
-    match self.create_new_vault(&path).map_err(|e| e.to_string()) {
-      Err(e) => self.show_error(&e),
-      Ok(_) => self.show_success("Vault created successfully!"),
-    };
+    self.show_success("Vault created successfully!");
   }
   
   /// Creating a new vault
   /// 
   /// # Errors
   /// Shows user an error if
+  /// - Another instance is creating the same vault
+  /// - The key file can't be read
   /// - Vault encryption fails
   /// - Filesystem save fails
   pub fn create_new_vault(&mut self, path: &str) -> Result<(), String> {
-    let plaintext = PasswordVault { entries: vec![] };
+    let key_file = self.selected_key_file()?;
+    let vault_lock = lock::acquire(path).map_err(|e| e.to_string())?;
+    let plaintext = PasswordVault::new(self.new_vault_name.trim());
+    let kdf = self
+      .new_vault_kdf
+      .as_ref()
+      .map(|c| c.params.clone())
+      .unwrap_or_default();
 
-    let encrypted = krypt::encrypt_vault(&plaintext, &self.master_password)?;
+    let mut key = krypt::new_session_key(&self.master_password, &kdf, key_file)?;
+    key.set_cipher(self.new_vault_cipher);
+    let recovery_key = if self.recovery_key_opt_in {
+      let recovery_key = krypt::generate_recovery_key();
+      key.set_recovery_key(&recovery_key)?;
+      Some(recovery_key)
+    } else {
+      None
+    };
+    let encrypted = krypt::encrypt_vault(&plaintext, &key)?;
 
-    vault::save(path, &encrypted)?;
+    let fingerprint = self.write_vault_file(path, &encrypted)?;
 
     self.vault = Some(plaintext);
     self.encrypted_vault = Some(encrypted);
+    self.session_key = Some(key);
+    self.vault_lock = Some(vault_lock);
+    self.read_only = false;
+    self.record_saved_state(Some(fingerprint));
+    self.new_recovery_key = recovery_key;
+    self.recovery_key_opt_in = false;
+    self.master_password.zeroize();
+    self.master_password_confirm.zeroize();
     Ok(())
   }
 
-  /// Reads filesystem available vaults
+  /// Reads filesystem available vaults and their unencrypted metadata
   pub fn reload_available_vaults(&mut self) {
-    self.available_vaults = vault::list_vaults();
+    self.available_vaults =
+      vault::list_vaults(&self.settings.vault_dirs, &self.settings.vault_files);
+    self.vault_metadata = self
+      .available_vaults
+      .iter()
+      .filter_map(|path| Some((path.clone(), vault::read_metadata(path)?)))
+      .collect();
+  }
+
+  /// Unencrypted metadata of an available vault, if it has any
+  pub fn get_vault_metadata(&self, path: &str) -> Option<&VaultMetadata> {
+    self.vault_metadata.get(path)
+  }
+
+  /// Name shown for a vault: its metadata name, or else its file name
+  pub fn vault_display_name(&self, path: &str) -> String {
+    self
+      .get_vault_metadata(path)
+      .map(|m| m.name.clone())
+      .unwrap_or_else(|| vault::display_name(path))
   }
 
-  /// Select an existing vault
+  /// Opens `FeatureState::VaultSettings` with the form filled from the vault
+  pub fn go_to_vault_settings(&mut self) {
+    let Some(vault) = self.vault.as_ref() else {
+      return;
+    };
+    if let Some(metadata) = &vault.metadata {
+      self.vault_name_input = metadata.name.clone();
+      self.vault_description_input = metadata.description.clone();
+    }
+    self.encrypt_metadata_input = vault.encrypt_metadata;
+    self.cipher_input = self.session_key.as_ref().map(|key| key.cipher()).unwrap_or_default();
+    if let AppState::Unlocked { feature_state } = &mut self.state {
+      *feature_state = FeatureState::VaultSettings;
+    }
+  }
+
+  /// Renames the vault and stores the rest of the vault settings form,
+  /// re-encrypting the vault if another cipher was picked.
+  /// The vault file keeps its path.
+  /// 
+  /// # Errors
+  /// Returns an error if the name is invalid or saving fails,
+  /// the vault then keeps its old settings
+  pub fn update_vault_metadata(&mut self) -> Result<(), String> {
+    vault::validate_name(&self.vault_name_input).map_err(|e| e.to_string())?;
+    let name = self.vault_name_input.trim();
+    let vault = self.vault.as_mut().ok_or("Vault locked")?;
+    let old_metadata = vault.metadata.clone();
+    let old_encrypt_metadata = vault.encrypt_metadata;
+
+    let metadata = vault.metadata.get_or_insert_with(|| VaultMetadata::new(name));
+    metadata.name = name.to_string();
+    metadata.description = self.vault_description_input.trim().to_string();
+    vault.encrypt_metadata = self.encrypt_metadata_input;
+    let cipher = self.cipher_input;
+    let result = self.update_session_key(|key| {
+      key.set_cipher(cipher);
+      Ok(())
+    });
+    if result.is_err()
+      && let Some(vault) = self.vault.as_mut()
+    {
+      vault.metadata = old_metadata;
+      vault.encrypt_metadata = old_encrypt_metadata;
+    }
+    result
+  }
+
+  /// Saves the settings file and rescans the vault list
+  /// 
+  /// # Errors
+  /// Shows the user an error if the settings file can't be written
+  pub fn settings_changed(&mut self) {
+    if let Err(e) = settings::save(&self.settings) {
+      self.show_error(format!("Failed to save settings: {}", e));
+    }
+    self.reload_available_vaults();
+  }
+
+  /// Watches the directory in `vault_dir_input` for vaults
+  /// 
+  /// # Errors
+  /// Returns an error if the path doesn't exist, isn't a directory, or is already watched
+  pub fn add_vault_dir(&mut self) -> Result<(), String> {
+    let dir = settings::absolute_path(&self.vault_dir_input)?;
+    if !std::path::Path::new(&dir).is_dir() {
+      return Err(format!("{} is not a directory", dir));
+    } else if self.settings.vault_dirs.contains(&dir) {
+      return Err("Directory is already added".into());
+    }
+    self.settings.vault_dirs.push(dir);
+    self.vault_dir_input.clear();
+    self.settings_changed();
+    Ok(())
+  }
+
+  /// Stops watching a vault directory, the vaults inside are left alone
+  pub fn remove_vault_dir(&mut self, index: usize) {
+    if index < self.settings.vault_dirs.len() {
+      self.settings.vault_dirs.remove(index);
+      self.new_vault_dir = 0;
+      self.settings_changed();
+    }
+  }
+
+  /// Registers the vault file in `vault_file_input`, wherever it is
+  /// 
+  /// # Errors
+  /// Returns an error if the path doesn't exist or isn't a readable vault
+  pub fn register_vault_file(&mut self) -> Result<(), String> {
+    let path = settings::absolute_path(&self.vault_file_input)?;
+    vault::load(&path)?;
+    if !self.settings.vault_files.contains(&path) {
+      self.settings.vault_files.push(path);
+    }
+    self.vault_file_input.clear();
+    self.settings_changed();
+    Ok(())
+  }
+
+  /// Whether a vault was registered individually rather than found in a directory
+  pub fn is_registered_vault_file(&self, path: &str) -> bool {
+    self.settings.vault_files.iter().any(|f| f == path)
+  }
+
+  /// Removes a registered vault file from the list, the file is left alone
+  pub fn forget_vault_file(&mut self, path: &str) {
+    self.settings.vault_files.retain(|f| f != path);
+    self.settings_changed();
+  }
+
+  /// Select an existing vault and take its lock
+  /// 
+  /// If another instance holds the lock, `lock_conflict` is set instead,
+  /// so the user can choose to open it read-only.
   /// 
   /// # Errors
   /// Returns an error if:
-  /// - Vault fails to load
+  /// - Vault and its backup fail to load
+  /// - The lock file can't be created
   pub fn select_existing_vault(&mut self, path: String) -> Result<(), String> {
-    // vault::load(&path)?; // Same thing as below
-    let encrypted = vault::load(&path)?;
+    // Release any lock still held from a previous vault
+    self.vault_lock = None;
+    match lock::acquire(&path) {
+      Ok(vault_lock) => self.open_selected_vault(path, Some(vault_lock)),
+      Err(LockError::Held(owner)) => {
+        self.lock_conflict = Some((path, owner));
+        Ok(())
+      }
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  /// Opens the vault from `lock_conflict` without taking its lock.
+  /// 
+  /// # Errors
+  /// Returns an error if the vault and its backup fail to load
+  pub fn open_conflicted_read_only(&mut self) -> Result<(), String> {
+    let (path, _) = self.lock_conflict.take().ok_or("No vault selected")?;
+    self.open_selected_vault(path, None)
+  }
+
+  /// Opens the vault from `lock_conflict`, replacing the other instance's lock.
+  /// 
+  /// # Errors
+  /// Returns an error if the lock can't be replaced or the vault fails to load
+  pub fn take_over_conflicted_lock(&mut self) -> Result<(), String> {
+    let (path, _) = self.lock_conflict.take().ok_or("No vault selected")?;
+    let vault_lock = lock::force_acquire(&path).map_err(|e| e.to_string())?;
+    self.open_selected_vault(path, Some(vault_lock))
+  }
+
+  /// Loads the selected vault and moves to `AppState::OldVault`.
+  /// Without a lock the vault is opened read-only.
+  fn open_selected_vault(&mut self, path: String, vault_lock: Option<VaultLock>) -> Result<(), String> {
+    let (encrypted, source) = vault::load_with_backup(&path)?;
+    if source == vault::LoadSource::Backup {
+      self.show_warning("Vault file is corrupt or missing, opened its backup instead");
+    }
+    self.read_only = vault_lock.is_none();
+    self.vault_lock = vault_lock;
     self.encrypted_vault = Some(encrypted);
     self.selected_vault = Some(path.to_string());
     self.state = AppState::OldVault;
@@ -257,11 +918,88 @@ impl PixelVaultApp {
     Ok(())
   }
 
-  /// Change to `AppState::NewVault`
+  /// Change to `AppState::NewVault` and calibrate the KDF for this machine
   pub fn go_to_vault_creation(&mut self) {
     self.state = AppState::NewVault;
+    if self.new_vault_kdf.is_none() {
+      self.start_kdf_calibration();
+    }
+  }
+
+  /// Benchmarks Argon2 on a background thread so the UI stays responsive.
+  /// The result is picked up by `poll_kdf_calibration`.
+  pub fn start_kdf_calibration(&mut self) {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+      sender.send(krypt::calibrate_kdf(krypt::CALIBRATION_TARGET)).ok();
+    });
+    self.new_vault_kdf = None;
+    self.kdf_calibration = Some(receiver);
+  }
+
+  /// Stores the calibration result once the background thread finishes.
+  /// 
+  /// # Errors
+  /// Shows the user a warning and falls back to default costs if calibration failed
+  pub fn poll_kdf_calibration(&mut self) {
+    let result = match self.kdf_calibration.as_ref().map(|r| r.try_recv()) {
+      Some(Ok(result)) => result,
+      Some(Err(mpsc::TryRecvError::Empty)) | None => return,
+      Some(Err(mpsc::TryRecvError::Disconnected)) => Err(VaultError::Crypto("Calibration thread stopped".into())),
+    };
+    self.kdf_calibration = None;
+    match result {
+      Ok(calibration) => self.new_vault_kdf = Some(calibration),
+      Err(e) => self.show_warning(format!("KDF calibration failed, using defaults: {}", e)),
+    }
+  }
+
+  /// Whether a KDF calibration is still running
+  pub fn is_calibrating_kdf(&self) -> bool {
+    self.kdf_calibration.is_some()
   }
   
+  /// Change to `AppState::RestoreBackup` for a backup of the vault at `path`
+  pub fn go_to_restore_backup(&mut self, path: String, backup: vault::BackupInfo) {
+    self.selected_vault = Some(path);
+    self.restore_backup = Some(backup);
+    self.state = AppState::RestoreBackup;
+  }
+
+  /// Restores `restore_backup` over the selected vault, once
+  /// `master_password` is confirmed to decrypt the backup.
+  /// 
+  /// # Returns
+  /// Success string
+  /// 
+  /// # Errors
+  /// Returns an error if:
+  /// - No backup or vault is selected
+  /// - The vault is open in another instance
+  /// - The backup can't be loaded or decrypted with the master password and key file
+  /// - Filesystem save fails
+  pub fn attempt_restore_backup(&mut self) -> Result<String, String> {
+    let backup = self.restore_backup.clone().ok_or("No backup selected")?;
+    let path = self.selected_vault.clone().ok_or("No vault selected")?;
+
+    let _vault_lock = lock::acquire(&path).map_err(|e| e.to_string())?;
+    let encrypted = vault::load(&backup.path)?;
+    let key_file = self.selected_key_file()?;
+    if let Err(e) = krypt::unlock_vault(&encrypted, &self.master_password, key_file.as_ref()) {
+      self.master_password.zeroize();
+      return Err(match e {
+        VaultError::AuthenticationFailed => "Incorrect master password for this backup".into(),
+        e => e.to_string(),
+      });
+    }
+    self.write_vault_file(&path, &encrypted)?;
+
+    let restored = Self::format_local_time(&backup.created);
+    let message = format!("Restored backup from {}", restored);
+    self.back_to_vaults();
+    Ok(message)
+  }
+
   /// Change to `AppState::Help`
   pub fn go_to_help(&mut self) {
     self.state = AppState::Help;
@@ -270,43 +1008,187 @@ impl PixelVaultApp {
   /// Delete a vault
   /// 
   /// # Errors
-  /// Returns an error if the filesystem delete fails
+  /// Returns an error if the vault is open in another instance,
+  /// or the filesystem delete fails
   pub fn delete_vault(&mut self, path: &String) -> Result<(), String> {
-    vault::delete(path)
+    let _vault_lock = lock::acquire(path).map_err(|e| e.to_string())?;
+    vault::delete(path)?;
+    if self.is_registered_vault_file(path) {
+      self.forget_vault_file(path);
+    }
+    Ok(())
   }
 
   /// Reset PixelVaultApp struct data and exits the vault.
   pub fn lock_vault(&mut self) {
+    self.close_vault();
+    self.show_info("Vault locked");
+  }
+
+  /// Wipes the unlocked vault and its forms, and goes back to the vault list
+  fn close_vault(&mut self) {
     self.state = AppState::SelectVault;
-    self.master_password.clear();
-    self.master_password_confirm.clear();
-    self.show_password_index = None;
+    self.clipboard.clear();
+    self.master_password.zeroize();
+    self.master_password_confirm.zeroize();
+    self.key_file_path.clear();
+    self.current_password_check.zeroize();
+    self.new_master_password.zeroize();
+    self.new_master_password_confirm.zeroize();
+    self.new_slot_password.zeroize();
+    self.new_slot_password_confirm.zeroize();
+    self.recovery_key_input.zeroize();
+    self.new_recovery_key = None;
+    self.recovery_key_written_down = false;
+    self.must_reset_password = false;
+    self.use_recovery_key = false;
+    self.show_password_id = None;
+    self.delete_confirmation_id = None;
+    self.edit_draft = None;
     self.new_service.clear();
     self.new_username.clear();
-    self.new_password.clear();
+    self.new_password.zeroize();
+    self.new_urls.clear();
+    self.edit_urls.clear();
+    self.pw_gen.generated_password.zeroize();
+    // Entries and the session key wipe themselves on drop
     self.vault = None;
+    self.session_key = None;
+    self.base_vault = None;
+    self.loaded_fingerprint = None;
+    self.pending_merge = None;
+    self.vault_lock = None;
+    self.read_only = false;
     self.selected_vault = None;
-    self.show_info("Vault locked");
+    self.last_activity = None;
+    self.background_since = None;
+    // Pick up renames and entry counts
+    self.reload_available_vaults();
   }
 
-  /// Function to try to unlock the vault using self.master_password,
-  /// 
-  /// # Returns
-  /// False and true based on success
-  pub fn unlock(&mut self, path: &str) -> bool {
-    let encrypted = match vault::load(&path) {
-      Ok(v) => v,
-      Err(_) => return false,
+  /// Time left before the vault locks itself, the sooner of the idle and
+  /// background timeouts in `settings.auto_lock`. `None` if neither applies.
+  pub fn auto_lock_remaining(&self) -> Option<Duration> {
+    let policy = &self.settings.auto_lock;
+    let deadline = |since: Option<Instant>, secs: u64| {
+      since.filter(|_| secs > 0).map(|since| since + Duration::from_secs(secs))
     };
+    let deadline = [
+      deadline(self.last_activity, policy.idle_secs),
+      deadline(self.background_since, policy.background_secs),
+    ]
+    .into_iter()
+    .flatten()
+    .min()?;
+    Some(deadline.saturating_duration_since(Instant::now()))
+  }
 
-    let plaintext = match krypt::decrypt_vault(&encrypted, &self.master_password) {
-      Ok(v) => v,
-      Err(_) => return false,
+  /// Tracks input and window focus while unlocked, and locks the vault
+  /// once an auto-lock timeout runs out.
+  fn check_auto_lock(&mut self, ctx: &egui::Context) {
+    if !matches!(self.state, AppState::Unlocked { .. }) {
+      return;
+    }
+    let now = Instant::now();
+    let (active, background) = ctx.input(|i| {
+      let viewport = i.viewport();
+      (
+        i.events.iter().any(is_user_input),
+        viewport.focused == Some(false) || viewport.minimized == Some(true),
+      )
+    });
+    if active || self.last_activity.is_none() {
+      self.last_activity = Some(now);
+    }
+    if !background {
+      self.background_since = None;
+    } else if self.background_since.is_none() {
+      self.background_since = Some(now);
+    }
+
+    match self.auto_lock_remaining() {
+      Some(remaining) if remaining.is_zero() => {
+        self.close_vault();
+        self.show_info("Vault locked after inactivity");
+      }
+      // Keep the countdown ticking without input
+      Some(remaining) => ctx.request_repaint_after(remaining.min(Duration::from_secs(1))),
+      None => {}
+    }
+  }
+
+  /// Reads the key file in `key_file_path`, if one is picked
+  /// 
+  /// # Errors
+  /// Returns [`VaultError::KeyFile`] if the file can't be read
+  fn selected_key_file(&self) -> Result<Option<KeyFile>, VaultError> {
+    let path = self.key_file_path.trim();
+    if path.is_empty() {
+      return Ok(None);
+    }
+    KeyFile::read(path).map(Some)
+  }
+
+  /// Function to try to unlock the vault using self.master_password, or
+  /// self.recovery_key_input when `use_recovery_key` is set
+  /// 
+  /// A vault file that is corrupted inside its encrypted data is only noticed
+  /// once the key is right, and then its backup is tried as well.
+  /// 
+  /// # Errors
+  /// Returns the [`VaultError`] of loading or decrypting the vault
+  pub fn unlock(&mut self, path: &str) -> Result<(), VaultError> {
+    let (mut encrypted, mut source) = vault::load_with_backup(path)?;
+    let mut opened = self.open_encrypted(&encrypted);
+    if matches!(opened, Err(VaultError::DataTampered))
+      && matches!(source, vault::LoadSource::Primary(_))
+      && let Ok(backup) = vault::load(&vault::backup_path(path))
+      && let Ok(from_backup) = self.open_encrypted(&backup)
+    {
+      opened = Ok(from_backup);
+      encrypted = backup;
+      source = vault::LoadSource::Backup;
+    }
+    let (mut plaintext, key) = opened?;
+    let fingerprint = match source {
+      vault::LoadSource::Primary(fingerprint) => Some(fingerprint),
+      vault::LoadSource::Backup => {
+        self.show_warning("Vault file was unreadable, opened its backup instead");
+        None
+      }
     };
+    // Entries made before IDs get one now, saved with the next save
+    plaintext.assign_entry_ids(None);
+    // Vaults made before metadata are named after their file until renamed
+    if plaintext.metadata.is_none() {
+      plaintext.metadata = Some(VaultMetadata::new(&vault::display_name(path)));
+    }
 
+    if encrypted.header.version < VAULT_FORMAT_VERSION {
+      self.show_warning("Vault uses an older file format, it will be upgraded on the next save");
+    }
     self.encrypted_vault = Some(encrypted);
     self.vault = Some(plaintext);
-    true
+    self.must_reset_password = key.unlocked_with_recovery_key();
+    self.session_key = Some(key);
+    self.record_saved_state(fingerprint);
+    self.master_password.zeroize();
+    self.recovery_key_input.zeroize();
+    Ok(())
+  }
+
+  /// Decrypts `encrypted` with the master password and key file, or the
+  /// recovery key when `use_recovery_key` is set
+  fn open_encrypted(
+    &self,
+    encrypted: &EncryptedVault,
+  ) -> Result<(PasswordVault, SessionKey), VaultError> {
+    if self.use_recovery_key {
+      krypt::unlock_with_recovery_key(encrypted, &self.recovery_key_input)
+    } else {
+      let key_file = self.selected_key_file()?;
+      krypt::unlock_vault(encrypted, &self.master_password, key_file.as_ref())
+    }
   }
 
   /// Attempt to unlock the vault stored in the PixelVaultApp state data
@@ -316,53 +1198,57 @@ impl PixelVaultApp {
   /// 
   /// # Errors
   /// Returns an error if:
-  /// - Master password is empty
-  /// - `self.create_new_vault()` fails
+  /// - Master password (or recovery key) is empty
+  /// - No vault is selected
   /// - Master password is incorrect
   pub fn attempt_unlock(&mut self) -> Result<String, String> {
-    if self.master_password.is_empty() {
+    if !matches!(self.state, AppState::OldVault) {
+      return Ok("".into());
+    }
+    if self.use_recovery_key && self.recovery_key_input.trim().is_empty() {
+      return Err("Recovery key cannot be empty!".into());
+    } else if !self.use_recovery_key && self.master_password.is_empty() {
       return Err("Master password cannot be empty!".into());
     }
-    match self.state {
-      AppState::NewVault => {
-        // Make a new vault
-        let path = self
-          .selected_vault
-          .clone()
-          .unwrap_or_else(|| "vaults/new_vault.json".to_string());
-        self.create_new_vault(&path)?;
+    let path = self.get_selected_vault().clone().ok_or("No vault selected!")?;
+    match self.unlock(&path) {
+      Ok(()) => {
+        self.unlock_error = None;
+        self.use_recovery_key = false;
+        if self.must_reset_password {
+          self.state = AppState::Unlocked {
+            feature_state: FeatureState::ChangeMasterPassword
+          };
+          return Ok("Vault recovered, choose a new master password".into());
+        }
         self.state = AppState::Unlocked {
           feature_state: FeatureState::default()
         };
-        return Ok("Vault created successfully!".into());
+        Ok("Vault unlocked!".into())
       }
-      AppState::OldVault => {
-        let path = match self.get_selected_vault().clone() {
-          Some(p) => p,
-          None => {
-            return Err("No vault selected!".into());
-          }
-        };
-        if self.unlock(&path) {
-          self.state = AppState::Unlocked {
-            feature_state: FeatureState::default()
-          };
-          return Ok("Vault unlocked!".into());
-        } else {
-          self.master_password.clear();
-          return Err("Incorrect Master Password".into());
-        }
+      Err(e) => {
+        self.master_password.zeroize();
+        self.recovery_key_input.zeroize();
+        let message = e.to_string();
+        self.unlock_error = Some(e);
+        Err(message)
       }
-      _ => {}
     }
-    Ok("".into())
   }
 
   /// Add a `PasswordEntry` to the vault entries based on PixelVaultApp state data
   /// 
   /// # Errors
-  /// Shows the user an error if the vault is still locked or saving fails
+  /// Shows the user an error if the vault is still locked, a website isn't
+  /// a valid URL, or saving fails
   pub fn add_entry(&mut self) {
+    let urls = match domain::normalize_url_list(&self.new_urls) {
+      Ok(urls) => urls,
+      Err(e) => {
+        self.show_error(e);
+        return;
+      }
+    };
     let vault = match self.vault.as_mut() {
       Some(v) => v,
       None => {
@@ -371,43 +1257,145 @@ impl PixelVaultApp {
       }
     };
 
-    vault.entries.push(PasswordEntry {
-      service: self.new_service.clone(),
-      username: self.new_username.clone(),
-      password: self.new_password.clone(),
-    });
+    let mut entry = PasswordEntry::new(
+      &self.new_service,
+      &self.new_username,
+      &self.new_password,
+    );
+    entry.urls = urls;
+    vault.entries.push(entry);
 
     self.new_service.clear();
     self.new_username.clear();
-    self.new_password.clear();
+    self.new_password.zeroize();
+    self.new_urls.clear();
 
     self.save_vault().unwrap_or_else(|e| {
       self.show_error(e.to_string());
     })
   }
 
-  /// Delete an entry at a given index. Decrements show_password_index for bounds safety
+  /// Delete the entry with the given ID
   /// 
   /// # Returns
   /// Shows user an info popup if password is removed
-  pub fn delete_entry(&mut self, index: usize) {
-    if let Some(vault) = &mut self.vault {
-      if index < vault.entries.len() {
-        let service = vault.entries[index].service.clone();
-        vault.entries.remove(index);
-
-        self.show_info(format!("Deleted password for {}", service));
-
-        // Delete password show if the entry is deleted
-        if self.show_password_index == Some(index) {
-          self.show_password_index = None;
-        } else if let Some(pass_idx) = self.show_password_index {
-          // Take one away if the index is after the deleted entry
-          if pass_idx > index {
-            self.show_password_index = Some(pass_idx - 1);
-          }
-        }
+  pub fn delete_entry(&mut self, id: Uuid) {
+    let Some(vault) = &mut self.vault else {
+      return;
+    };
+    let Some(index) = vault.entries.iter().position(|entry| entry.id == id) else {
+      return;
+    };
+    let entry = vault.entries.remove(index);
+    self.show_info(format!("Deleted password for {}", entry.service));
+    if self.show_password_id == Some(id) {
+      self.show_password_id = None;
+    }
+    self.delete_confirmation_id = None;
+    if self.edit_draft.as_ref().is_some_and(|draft| draft.id == id) {
+      self.cancel_edit_entry();
+    }
+  }
+
+  /// Opens `FeatureState::EditEntry` with a draft copy of the entry
+  pub fn go_to_edit_entry(&mut self, id: Uuid) {
+    let Some(entry) = self.vault.as_ref().and_then(|vault| vault.entry(id)) else {
+      return;
+    };
+    self.edit_urls = entry.urls.join("\n");
+    self.edit_draft = Some(entry.clone());
+    if let AppState::Unlocked { feature_state } = &mut self.state {
+      *feature_state = FeatureState::EditEntry;
+    }
+  }
+
+  /// Writes the edit draft back to its entry and saves the vault.
+  /// A changed password moves the old one into the entry's history.
+  /// 
+  /// # Errors
+  /// Returns an error if:
+  /// - The service or password is empty
+  /// - A website isn't a valid URL
+  /// - The entry was deleted meanwhile (e.g. by a merge)
+  /// - Saving fails, the draft is kept to try again and the entry is left as it was
+  pub fn save_edited_entry(&mut self) -> Result<(), String> {
+    let draft = self.edit_draft.clone().ok_or("No entry is being edited")?;
+    if draft.service.trim().is_empty() {
+      return Err("Service cannot be empty".into());
+    } else if draft.password.is_empty() {
+      return Err("Password cannot be empty".into());
+    }
+    let urls = domain::normalize_url_list(&self.edit_urls)?;
+    let max_history = self.settings.max_password_history;
+    self.update_entry(draft.id, |entry| {
+      if entry.service != draft.service || entry.username != draft.username || entry.urls != urls {
+        entry.service = draft.service.clone();
+        entry.username = draft.username.clone();
+        entry.urls = urls;
+        entry.modified_at = chrono::Utc::now();
       }
+      entry.set_password(&draft.password, max_history);
+    })?;
+    self.cancel_edit_entry();
+    Ok(())
+  }
+
+  /// Changes a copy of an entry, swaps it in and saves the vault.
+  /// If saving fails the entry is put back as it was, so nothing unsaved
+  /// is left in the vault to be written by a later save.
+  /// 
+  /// Changes made elsewhere are merged before the edit, so a merge waiting
+  /// on the user never contains an edit that was reported as failed.
+  /// 
+  /// # Errors
+  /// Returns an error if the vault is read-only, the file changed elsewhere
+  /// and can't be merged without the user, the entry no longer exists or saving fails
+  fn update_entry(
+    &mut self,
+    id: Uuid,
+    update: impl FnOnce(&mut PasswordEntry),
+  ) -> Result<(), String> {
+    if self.read_only {
+      return Err("Vault is open read-only".into());
+    }
+    self.merge_external_changes()?;
+    let vault = self.vault.as_mut().ok_or("Vault locked")?;
+    let entry = vault.entry_mut(id).ok_or("Entry no longer exists")?;
+    let mut updated = entry.clone();
+    update(&mut updated);
+    let original = std::mem::replace(entry, updated);
+
+    let result = self.save_vault();
+    if result.is_err()
+      && let Some(entry) = self.vault.as_mut().and_then(|vault| vault.entry_mut(id))
+    {
+      *entry = original;
+      // The file may have changed again since the merge above
+      if let (Some(pending), Some(base), Some(mine)) =
+        (self.pending_merge.as_mut(), &self.base_vault, &self.vault)
+      {
+        pending.outcome = merge::three_way(base, mine, &pending.theirs);
+      }
+    }
+    result
+  }
+
+  /// Makes an old password of an entry current again and saves the vault
+  /// 
+  /// # Errors
+  /// Returns an error if the entry no longer exists or saving fails,
+  /// the entry is then left as it was
+  pub fn restore_password(&mut self, id: Uuid, history_index: usize) -> Result<(), String> {
+    let max_history = self.settings.max_password_history;
+    self.update_entry(id, |entry| entry.restore_password(history_index, max_history))
+  }
+
+  /// Discards the edit draft and goes back to adding entries
+  pub fn cancel_edit_entry(&mut self) {
+    self.edit_draft = None;
+    self.edit_urls.clear();
+    if let AppState::Unlocked { feature_state } = &mut self.state {
+      *feature_state = FeatureState::default();
     }
   }
 
@@ -437,10 +1425,22 @@ impl PixelVaultApp {
   /// Clears the input boxes
   pub fn back_to_vaults(&mut self) {
     self.state = AppState::SelectVault;
+    self.unlock_error = None;
+    self.key_file_path.clear();
+    self.use_recovery_key = false;
+    self.recovery_key_input.zeroize();
+    self.recovery_key_opt_in = false;
+    self.restore_backup = None;
     self.new_vault_name.clear();
-    self.master_password.clear();
-    self.master_password_confirm.clear();
+    self.master_password.zeroize();
+    self.master_password_confirm.zeroize();
     self.vault = None;
+    self.session_key = None;
+    self.base_vault = None;
+    self.loaded_fingerprint = None;
+    self.pending_merge = None;
+    self.vault_lock = None;
+    self.read_only = false;
   }
   
   /// Return the readme as a string
@@ -451,8 +1451,54 @@ impl PixelVaultApp {
 
   
 
+/// Info toast with the seconds left until the copied password is cleared,
+/// closed early if the clipboard was cleared on lock
+fn clipboard_toast_contents(
+  ui: &mut egui::Ui,
+  toast: &mut Toast,
+  clear_deadline: &ClearDeadline,
+) -> egui::Response {
+  let deadline = clear_deadline.lock().ok().and_then(|deadline| *deadline);
+  let Some(deadline) = deadline else {
+    toast.close();
+    return ui.label("");
+  };
+  let secs = deadline.saturating_duration_since(Instant::now()).as_secs_f32().ceil();
+  egui::Frame::window(ui.style())
+    .inner_margin(10.0)
+    .show(ui, |ui| {
+      ui.horizontal(|ui| {
+        ui.label(toast.style.info_icon.clone());
+        ui.label(format!("{} Clears in {} s", toast.text.text(), secs));
+        if ui.button(toast.style.close_button_text.clone()).clicked() {
+          toast.close();
+        }
+      });
+    })
+    .response
+}
+
+/// Whether an input event came from the user, rather than the window system
+fn is_user_input(event: &egui::Event) -> bool {
+  matches!(
+    event,
+    egui::Event::Key { .. }
+      | egui::Event::Text(_)
+      | egui::Event::Paste(_)
+      | egui::Event::PointerMoved(_)
+      | egui::Event::PointerButton { .. }
+      | egui::Event::MouseWheel { .. }
+      | egui::Event::Touch { .. }
+  )
+}
+
 impl eframe::App for PixelVaultApp {
   fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+    self.check_auto_lock(ctx);
+    self.clipboard.clear_if_due();
+    if let Some(remaining) = self.clipboard.remaining() {
+      ctx.request_repaint_after(remaining);
+    }
     self.toasts.show(ctx);
     
     // Take ownership temporarily, then restore
@@ -463,6 +1509,7 @@ impl eframe::App for PixelVaultApp {
       AppState::NewVault => self.show_new_vault(ctx),
       AppState::OldVault => self.show_old_vault(ctx),
       AppState::Unlocked { .. } => self.show_unlocked(ctx),
+      AppState::RestoreBackup => self.show_restore_backup(ctx),
       AppState::Help => self.show_help(ctx),
       // _ => {
       //   self.lock_vault();
@@ -471,4 +1518,102 @@ impl eframe::App for PixelVaultApp {
       // }
     }
   }
+
+  /// Leaves nothing copied behind on the clipboard
+  fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+    self.clipboard.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::{TEST_KDF, sample_vault, temp_dir, temp_path};
+
+  /// Saves the sample vault at `path` for the password `pw`
+  fn save_sample(path: &str) {
+    let key = krypt::new_session_key("pw", &TEST_KDF, None).unwrap();
+    let encrypted = krypt::encrypt_vault(&sample_vault(), &key).unwrap();
+    vault::save(path, &encrypted, &vault::BackupPolicy::default()).unwrap();
+  }
+
+  #[test]
+  fn unlock_falls_back_to_bak_when_the_data_is_corrupt() {
+    let path = temp_path("app-corrupt", "vault.pvault");
+    save_sample(&path);
+    save_sample(&path);
+    // Flip a ciphertext byte, which only the right key notices
+    let mut data = std::fs::read(&path).unwrap();
+    *data.last_mut().unwrap() ^= 1;
+    std::fs::write(&path, &data).unwrap();
+
+    let mut app = PixelVaultApp {
+      master_password: Zeroizing::new("pw".into()),
+      ..Default::default()
+    };
+    app.unlock(&path).unwrap();
+    assert_eq!(app.vault.as_ref().unwrap().entries.len(), 1);
+    assert!(app.loaded_fingerprint.is_none());
+
+    std::fs::remove_file(vault::backup_path(&path)).unwrap();
+    app.master_password = Zeroizing::new("pw".into());
+    assert!(matches!(app.unlock(&path), Err(VaultError::DataTampered)));
+  }
+
+  #[test]
+  fn failed_creation_stays_on_the_new_vault_screen() {
+    let dir = temp_dir("app-create");
+    let mut app = PixelVaultApp {
+      state: AppState::NewVault,
+      new_vault_name: "Work".into(),
+      master_password: Zeroizing::new("pw".into()),
+      master_password_confirm: Zeroizing::new("pw".into()),
+      key_file_path: dir.join("missing.key").to_string_lossy().to_string(),
+      ..Default::default()
+    };
+    app.settings.vault_dirs = vec![dir.to_string_lossy().to_string()];
+
+    app.attempt_create_vault();
+    assert!(matches!(app.state, AppState::NewVault));
+    assert!(app.selected_vault.is_none() && app.vault.is_none());
+    let path = dir.join("work.pvault").to_string_lossy().to_string();
+    assert!(!std::fs::exists(&path).unwrap());
+    assert!(!std::fs::exists(lock::lock_path(&path)).unwrap());
+  }
+
+  #[test]
+  fn edit_failing_on_a_conflict_is_not_saved_by_the_merge() {
+    let path = temp_path("app-conflict", "vault.pvault");
+    let mut sample = sample_vault();
+    sample.entries.push(PasswordEntry::new("example.org", "me", "hunter3"));
+    let key = krypt::new_session_key("pw", &TEST_KDF, None).unwrap();
+    let encrypted = krypt::encrypt_vault(&sample, &key).unwrap();
+    vault::save(&path, &encrypted, &vault::BackupPolicy::default()).unwrap();
+    let mut app = PixelVaultApp {
+      master_password: Zeroizing::new("pw".into()),
+      selected_vault: Some(path.clone()),
+      ..Default::default()
+    };
+    app.unlock(&path).unwrap();
+    let [first, second] = [0, 1].map(|i| app.vault.as_ref().unwrap().entries[i].id);
+
+    // Both this and another instance change the second entry
+    app.vault.as_mut().unwrap().entry_mut(second).unwrap().password = "mine".into();
+    let mut theirs = app.base_vault.clone().unwrap();
+    theirs.entry_mut(second).unwrap().password = "theirs".into();
+    let encrypted = krypt::encrypt_vault(&theirs, &key).unwrap();
+    vault::save(&path, &encrypted, &vault::BackupPolicy::default()).unwrap();
+
+    app.go_to_edit_entry(first);
+    app.edit_draft.as_mut().unwrap().password = "edited".into();
+    assert!(app.save_edited_entry().is_err());
+    assert_eq!(app.vault.as_ref().unwrap().entry(first).unwrap().password, "hunter2");
+
+    let pending = app.pending_merge.as_mut().unwrap();
+    pending.outcome.conflicts[0].resolution = Some(merge::Resolution::Mine);
+    app.resolve_merge_conflicts().unwrap();
+    let (saved, _) = krypt::unlock_vault(&vault::load(&path).unwrap(), "pw", None).unwrap();
+    assert_eq!(saved.entry(first).unwrap().password, "hunter2");
+    assert_eq!(saved.entry(second).unwrap().password, "mine");
+  }
 }
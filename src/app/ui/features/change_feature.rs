@@ -5,19 +5,26 @@ impl PixelVaultApp {
   pub fn change_feature_widget(&mut self, ui: &mut egui::Ui) {
     ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
       ui.menu_button("Change Mode", |ui| {
-        if ui.button("Edit Entries").clicked() {
+        if ui.button("Add New Entries").clicked() {
           if let AppState::Unlocked { feature_state } = &mut self.state_mut() {
-            // Now you can modify feature_state
-            *feature_state = FeatureState::EditEntry;
+            *feature_state = FeatureState::NewEntry { show_pw_gen: false};
           }
           ui.close();
         }
-        if ui.button("Add New Entries").clicked() {
+        if ui.button("Change Master Password").clicked() {
           if let AppState::Unlocked { feature_state } = &mut self.state_mut() {
-            *feature_state = FeatureState::NewEntry { show_pw_gen: false};
+            *feature_state = FeatureState::ChangeMasterPassword;
           }
           ui.close();
         }
+        if ui.button("Manage Access").clicked() {
+          self.go_to_key_slots();
+          ui.close();
+        }
+        if ui.button("Vault Settings").clicked() {
+          self.go_to_vault_settings();
+          ui.close();
+        }
       });
     });
   }
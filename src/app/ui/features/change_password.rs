@@ -0,0 +1,57 @@
+use eframe::egui;
+use crate::app::{PixelVaultApp, app::{AppState, FeatureState}};
+
+impl PixelVaultApp {
+  /// UI depicting a form to rotate the master password of the unlocked vault
+  pub fn show_change_master_password(&mut self, ui: &mut egui::Ui) {
+    if self.must_reset_password {
+      // Unlocked with the recovery key, nothing else until a new password is set
+      ui.heading("Set a New Master Password");
+      ui.label("The vault was opened with its recovery key. Choose a new master password to continue.");
+    } else {
+      ui.columns_const(|[col1, col2]| {
+        col1.horizontal(|ui| {
+          ui.heading("Change Master Password");
+        });
+        col2.horizontal(|ui| {
+          self.change_feature_widget(ui);
+        });
+      });
+
+      ui.add(
+        egui::TextEdit::singleline(&mut *self.current_password_check)
+          .password(true)
+          .desired_width(ui.available_width())
+          .hint_text("Current master password"),
+      );
+    }
+    ui.add(
+      egui::TextEdit::singleline(&mut *self.new_master_password)
+        .password(true)
+        .desired_width(ui.available_width())
+        .hint_text("New master password"),
+    );
+    ui.add(
+      egui::TextEdit::singleline(&mut *self.new_master_password_confirm)
+        .password(true)
+        .desired_width(ui.available_width())
+        .hint_text("Confirm new master password"),
+    );
+
+    Self::show_password_strength(ui, &self.new_master_password);
+
+    ui.horizontal(|ui| {
+      if ui.button("Change Password").clicked() {
+        match self.change_master_password() {
+          Ok(_) => {
+            self.show_success("Master password changed!");
+            if let AppState::Unlocked { feature_state } = self.state_mut() {
+              *feature_state = FeatureState::default();
+            }
+          }
+          Err(e) => self.show_error(e),
+        }
+      }
+    });
+  }
+}
@@ -1,7 +1,8 @@
 use eframe::egui;
-use crate::{app::PixelVaultApp};
+use crate::{app::PixelVaultApp, domain, pw_gen::PasswordGenerator};
 
 impl PixelVaultApp{
+  /// UI depicting a form to edit a draft copy of an entry, saved or discarded as a whole
   pub fn show_edit_entry(&mut self, ui: &mut egui::Ui) {
     ui.columns_const(|[col1, col2]| {
       col1.horizontal(|ui| {
@@ -12,49 +13,58 @@ impl PixelVaultApp{
       });
     });
     
-    if self.vault.is_none() {
-      ui.colored_label(egui::Color32::RED, "Vault doesn't exist!");
+    let Some(draft) = self.edit_draft.as_mut() else {
+      ui.label("Pick an entry to edit with its Edit button.");
       return;
-    }
-    let edit_index = match self.edit_index {
-      Some(i) => i,
-      None => 0,
     };
-    
-    let vault = self.vault.as_mut().unwrap();
-    // Check edit index out of bounds
-    if edit_index >= vault.entries.len() {
-      ui.colored_label(egui::Color32::RED, "Entry doesn't exist!");
-      return;
-    }
-    
-    let entry = &mut vault.entries[edit_index];
-    
 
     ui.horizontal(|ui| {
       ui.label("Service:");
-      ui.add(
-        egui::TextEdit::singleline(&mut entry.username)
-      )
-      
+      ui.text_edit_singleline(&mut draft.service);
     });
+    if draft.service.trim().is_empty() {
+      ui.colored_label(egui::Color32::RED, "Service cannot be empty");
+    }
 
     ui.horizontal(|ui| {
       ui.label("Username:");
-      ui.text_edit_singleline(&mut entry.service);
+      ui.text_edit_singleline(&mut draft.username);
     });
 
+    ui.horizontal(|ui| {
+      ui.label("Websites:");
+      ui.add(
+        egui::TextEdit::multiline(&mut self.edit_urls)
+          .desired_rows(1)
+          .hint_text("example.com, one per line"),
+      );
+    });
+    if let Err(e) = domain::normalize_url_list(&self.edit_urls) {
+      ui.colored_label(egui::Color32::RED, e);
+    }
+
     ui.horizontal(|ui| {
       ui.label("Password:");
-      ui.add(egui::TextEdit::singleline(&mut entry.password));
+      ui.add(egui::TextEdit::singleline(&mut draft.password));
+    });
+    if draft.password.is_empty() {
+      ui.colored_label(egui::Color32::RED, "Password cannot be empty");
+    }
+    let pass_str = PasswordGenerator::calc_strength(&draft.password);
+    ui.horizontal(|ui| {
+      ui.label("Password Strength:");
+      ui.label(pass_str);
     });
 
     ui.horizontal(|ui| {
       if ui.button("Save").clicked() {
-        // handle save
+        match self.save_edited_entry() {
+          Ok(_) => self.show_success("Entry saved!"),
+          Err(e) => self.show_error(e),
+        }
       }
       if ui.button("Cancel").clicked() {
-        // Handle cancel
+        self.cancel_edit_entry();
       }
     });
   }
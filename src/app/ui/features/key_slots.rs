@@ -0,0 +1,87 @@
+use eframe::egui;
+use crate::app::PixelVaultApp;
+
+impl PixelVaultApp {
+  /// UI depicting the key slots of the vault, with a form to add one
+  pub fn show_key_slots(&mut self, ui: &mut egui::Ui) {
+    ui.columns_const(|[col1, col2]| {
+      col1.horizontal(|ui| {
+        ui.heading("Manage Access");
+      });
+      col2.horizontal(|ui| {
+        self.change_feature_widget(ui);
+      });
+    });
+    ui.label("Each key slot is a password (and key file) that opens this vault.");
+
+    let mut remove_index = None;
+    if let Some(slots) = self.get_key_slots() {
+      for (i, slot) in slots.iter().enumerate() {
+        ui.horizontal(|ui| {
+          let icon = if slot.recovery { "🛟" } else { "🔑" };
+          let mut text = format!("{} {}", icon, slot.label);
+          if slot.key_file {
+            text.push_str(" + key file");
+          }
+          ui.label(text);
+          ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if self.is_unlocked_slot(slot) {
+              ui.label(egui::RichText::new("in use").weak());
+            } else if ui.small_button("Revoke").clicked() {
+              remove_index = Some(i);
+            }
+          });
+        });
+      }
+    }
+    if let Some(i) = remove_index {
+      match self.remove_key_slot(i) {
+        Ok(_) => self.show_success("Key slot revoked"),
+        Err(e) => self.show_error(e),
+      }
+    }
+
+    let recovery_text = if self.has_recovery_key() {
+      "Replace Recovery Key"
+    } else {
+      "Make Recovery Key"
+    };
+    if ui
+      .button(recovery_text)
+      .on_hover_text("A code that opens the vault if the master password is lost")
+      .clicked()
+      && let Err(e) = self.create_recovery_key()
+    {
+      self.show_error(e);
+    }
+
+    ui.separator();
+    ui.add(
+      egui::TextEdit::singleline(&mut self.new_slot_label)
+        .desired_width(ui.available_width())
+        .hint_text("Label, e.g. who the slot is for"),
+    );
+    ui.add(
+      egui::TextEdit::singleline(&mut *self.new_slot_password)
+        .password(true)
+        .desired_width(ui.available_width())
+        .hint_text("Password"),
+    );
+    ui.add(
+      egui::TextEdit::singleline(&mut *self.new_slot_password_confirm)
+        .password(true)
+        .desired_width(ui.available_width())
+        .hint_text("Confirm password"),
+    );
+    self.show_key_file_picker(ui, true);
+
+    Self::show_password_strength(ui, &self.new_slot_password);
+
+    if ui.button("Add Key Slot").clicked() {
+      match self.add_key_slot() {
+        Ok(_) => self.show_success("Key slot added!"),
+        Err(e) => self.show_error(e),
+      }
+    }
+  }
+}
@@ -1,4 +1,7 @@
 pub mod new_entry;
 pub mod edit_entry;
 pub mod change_feature;
-pub mod pw_gen;
\ No newline at end of file
+pub mod pw_gen;
+pub mod change_password;
+pub mod vault_settings;
+pub mod key_slots;
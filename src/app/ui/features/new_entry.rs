@@ -1,10 +1,8 @@
-use std::ops::Div;
-
 use eframe::egui;
-use crate::{app::{PixelVaultApp, app::{AppState, FeatureState}}, pw_gen::{self, PasswordGenerator}};
+use crate::{app::{PixelVaultApp, app::{AppState, FeatureState}}, pw_gen::PasswordGenerator};
 
 impl PixelVaultApp{
-  /// UI depicting a form to add a new entry (username, service, password)
+  /// UI depicting a form to add a new entry (username, service, websites, password)
   pub fn show_new_entry(&mut self, ui: &mut egui::Ui) {
     
     ui.columns_const(|[col1, col2]| {
@@ -25,10 +23,19 @@ impl PixelVaultApp{
       ui.label("Username:");
       ui.text_edit_singleline(&mut self.new_username);
     });
+
+    ui.horizontal(|ui| {
+      ui.label("Websites:");
+      ui.add(
+        egui::TextEdit::multiline(&mut self.new_urls)
+          .desired_rows(1)
+          .hint_text("example.com, one per line"),
+      );
+    });
     
     ui.horizontal(|ui| {
       ui.label("Password:");
-      ui.add(egui::TextEdit::singleline(&mut self.new_password));
+      ui.add(egui::TextEdit::singleline(&mut *self.new_password));
     });
     let pass_str = PasswordGenerator::calc_strength(&self.new_password);
     // let pass_score = PasswordGenerator::get_password_score(&self.new_password);
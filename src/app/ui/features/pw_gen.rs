@@ -1,5 +1,6 @@
 use eframe::egui::{self, Color32, RichText, vec2};
 use crate::app::app::PixelVaultApp;
+use zeroize::Zeroizing;
 
 impl PixelVaultApp {
   pub(crate) fn show_password_generator(&mut self, ui: &mut egui::Ui) {
@@ -22,16 +23,16 @@ impl PixelVaultApp {
                 )
               );
               if gen_pass_response.clicked() {
-                ui.ctx().copy_text(self.pw_gen.generated_password.clone());
-                self.show_info("Generated Password copied!");
+                let password = Zeroizing::new(self.pw_gen.generated_password.clone());
+                self.copy_secret(&password, "Generated password");
               }
             });
         });
         col2.horizontal(|ui| {
           ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
             if ui.button("Copy").clicked() {
-              ui.ctx().copy_text(self.pw_gen.generated_password.clone());
-              self.show_info("Generated Password copied!");
+              let password = Zeroizing::new(self.pw_gen.generated_password.clone());
+              self.copy_secret(&password, "Generated password");
             }
           });
         })
@@ -0,0 +1,70 @@
+use eframe::egui;
+use crate::{app::{PixelVaultApp, app::{AppState, FeatureState}}, models::CipherKind, vault};
+
+impl PixelVaultApp {
+  /// UI depicting a form to rename the unlocked vault and edit its metadata
+  pub fn show_vault_settings(&mut self, ui: &mut egui::Ui) {
+    ui.columns_const(|[col1, col2]| {
+      col1.horizontal(|ui| {
+        ui.heading("Vault Settings");
+      });
+      col2.horizontal(|ui| {
+        self.change_feature_widget(ui);
+      });
+    });
+
+    ui.add(
+      egui::TextEdit::singleline(&mut self.vault_name_input)
+        .desired_width(ui.available_width())
+        .hint_text("Vault name"),
+    );
+    if let Err(e) = vault::validate_name(&self.vault_name_input) {
+      ui.colored_label(egui::Color32::RED, e.to_string());
+    }
+    ui.add(
+      egui::TextEdit::multiline(&mut self.vault_description_input)
+        .desired_rows(2)
+        .desired_width(ui.available_width())
+        .hint_text("Description"),
+    );
+    ui.checkbox(&mut self.encrypt_metadata_input, "Keep name and description encrypted")
+      .on_hover_text("The vault list will only show the file name of this vault");
+    ui.horizontal(|ui| {
+      ui.label("Cipher:");
+      egui::ComboBox::from_id_salt("vault_cipher")
+        .selected_text(self.cipher_input.name())
+        .show_ui(ui, |ui| {
+          for cipher in CipherKind::ALL {
+            ui.selectable_value(&mut self.cipher_input, cipher, cipher.name());
+          }
+        });
+    })
+    .response
+    .on_hover_text("Changing the cipher re-encrypts the vault when saved");
+
+    if let Some(metadata) = self.get_current_vault().and_then(|v| v.metadata.as_ref()) {
+      ui.label(
+        egui::RichText::new(format!(
+          "Created {}, last modified {}",
+          PixelVaultApp::format_local_time(&metadata.created),
+          PixelVaultApp::format_local_time(&metadata.modified),
+        ))
+        .weak(),
+      );
+    }
+
+    ui.horizontal(|ui| {
+      if ui.button("Save").clicked() {
+        match self.update_vault_metadata() {
+          Ok(_) => {
+            self.show_success("Vault settings saved!");
+            if let AppState::Unlocked { feature_state } = self.state_mut() {
+              *feature_state = FeatureState::default();
+            }
+          }
+          Err(e) => self.show_error(e),
+        }
+      }
+    });
+  }
+}
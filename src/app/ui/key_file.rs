@@ -0,0 +1,48 @@
+use crate::{app::PixelVaultApp, krypt};
+use eframe::egui;
+
+impl PixelVaultApp {
+  /// Row for picking the key file in `key_file_path`.
+  /// With `allow_generate`, it also offers to make a new random key file.
+  pub fn show_key_file_picker(&mut self, ui: &mut egui::Ui, allow_generate: bool) {
+    ui.horizontal(|ui| {
+      let buttons_width = if allow_generate { 110.0 } else { 60.0 };
+      ui.add(
+        egui::TextEdit::singleline(&mut self.key_file_path)
+          .desired_width(ui.available_width() - buttons_width)
+          .hint_text("Key file (optional)"),
+      )
+      .on_hover_text("A file needed as well as the master password to unlock, e.g. on a USB stick");
+      if ui.button("📂").on_hover_text("Select key file").clicked()
+        && let Some(path) = rfd::FileDialog::new().set_title("Select key file").pick_file()
+      {
+        self.key_file_path = path.to_string_lossy().to_string();
+      }
+      if allow_generate && ui.button("Generate").on_hover_text("Make a new random key file").clicked() {
+        self.generate_key_file();
+      }
+      if !self.key_file_path.is_empty() && ui.small_button("✖").on_hover_text("No key file").clicked() {
+        self.key_file_path.clear();
+      }
+    });
+  }
+
+  /// Asks where to save a new random key file, writes it and picks it
+  fn generate_key_file(&mut self) {
+    let Some(path) = rfd::FileDialog::new()
+      .set_title("Save new key file")
+      .set_file_name("pixelvault.key")
+      .save_file()
+    else {
+      return;
+    };
+    let path = path.to_string_lossy().to_string();
+    match krypt::generate_key_file(&path) {
+      Ok(_) => {
+        self.key_file_path = path;
+        self.show_warning("Key file created. Keep a copy somewhere safe, the vault can't be opened without it");
+      }
+      Err(e) => self.show_error(e.to_string()),
+    }
+  }
+}
@@ -0,0 +1,15 @@
+use crate::app::PixelVaultApp;
+use chrono::{DateTime, Local, Utc};
+
+impl PixelVaultApp {
+  /// Static helper showing a timestamp in the local time zone, to the minute
+  pub fn format_local_time(date: &DateTime<Utc>) -> String {
+    date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+  }
+
+  /// Like [`Self::format_local_time`] but to the second, so backups made
+  /// within the same minute can be told apart
+  pub fn format_local_time_precise(date: &DateTime<Utc>) -> String {
+    date.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+  }
+}
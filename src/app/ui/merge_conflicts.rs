@@ -0,0 +1,68 @@
+use eframe::egui;
+use crate::{app::PixelVaultApp, merge::Resolution, models::PasswordEntry};
+
+impl PixelVaultApp {
+  /// Modal listing entries changed both here and elsewhere, to pick a side for each
+  pub fn show_merge_conflicts(&mut self, ctx: &egui::Context) {
+    let Some(pending) = self.pending_merge.as_mut() else {
+      return;
+    };
+    let mut apply_clicked = false;
+
+    egui::Modal::new(egui::Id::new("merge_conflicts")).show(ctx, |ui| {
+      ui.heading("Vault changed elsewhere");
+      ui.label("These entries were changed both here and in another copy of the vault.");
+      ui.label("Pick which version to keep for each one.");
+      ui.separator();
+
+      let count = pending.outcome.conflicts.len();
+      egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+        for (i, conflict) in pending.outcome.conflicts.iter_mut().enumerate() {
+          if let Some(entry) = conflict.entry() {
+            ui.label(format!("🌐 {} 👤 {}", entry.service, entry.username));
+          }
+          let sides = [
+            (Resolution::Mine, "This copy", &conflict.mine),
+            (Resolution::Theirs, "Other copy", &conflict.theirs),
+          ];
+          for (side, name, entry) in sides {
+            let response = ui.radio(
+              conflict.resolution == Some(side),
+              format!("{}: {}", name, describe(entry)),
+            );
+            if response.clicked() {
+              conflict.resolution = Some(side);
+            }
+            if let Some(entry) = entry {
+              response.on_hover_text(format!("🔑 {}", entry.password));
+            }
+          }
+          if i + 1 < count {
+            ui.separator();
+          }
+        }
+      });
+
+      ui.add_space(10.0);
+      let all_picked = pending.outcome.conflicts.iter().all(|c| c.resolution.is_some());
+      if ui.add_enabled(all_picked, egui::Button::new("Apply and Save")).clicked() {
+        apply_clicked = true;
+      }
+    });
+
+    if apply_clicked {
+      match self.resolve_merge_conflicts() {
+        Ok(_) => self.show_success("Merged and saved"),
+        Err(e) => self.show_error(e),
+      }
+    }
+  }
+}
+
+/// Short description of one side of a conflict, the password is only shown on hover
+fn describe(entry: &Option<PasswordEntry>) -> String {
+  match entry {
+    Some(_) => "kept, hover to see the password".to_string(),
+    None => "deleted".to_string(),
+  }
+}
@@ -1,6 +1,12 @@
 pub mod fancy_frame;
+pub mod key_file;
+pub mod local_time;
+pub mod merge_conflicts;
 pub mod new_vault;
 pub mod old_vault;
+pub mod password_strength;
+pub mod recovery_key;
+pub mod restore_backup;
 pub mod password_entry;
 pub mod select_vault;
 pub mod unlocked;
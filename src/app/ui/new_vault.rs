@@ -1,5 +1,5 @@
 use eframe::egui;
-use crate::app::PixelVaultApp;
+use crate::{app::PixelVaultApp, models::CipherKind, vault};
 
 impl PixelVaultApp {
   /// UI for opening a new vault
@@ -26,9 +26,34 @@ impl PixelVaultApp {
         if self.new_vault_name.is_empty() && !name_response.has_focus() {
           name_response.request_focus();
         }
+        if !self.new_vault_name.is_empty() {
+          match vault::file_stem_for(&self.new_vault_name) {
+            Ok(stem) => {
+              ui.label(egui::RichText::new(format!("Saved as {}.{}", stem, vault::VAULT_EXTENSION)).weak());
+            }
+            Err(e) => {
+              ui.colored_label(egui::Color32::RED, e.to_string());
+            }
+          }
+        }
+
+        if self.settings.vault_dirs.len() > 1 {
+          ui.horizontal(|ui| {
+            ui.label("Location:");
+            let dirs = &self.settings.vault_dirs;
+            let selected = dirs.get(self.new_vault_dir).cloned().unwrap_or_default();
+            egui::ComboBox::from_id_salt("new_vault_dir")
+              .selected_text(selected)
+              .show_ui(ui, |ui| {
+                for (i, dir) in dirs.iter().enumerate() {
+                  ui.selectable_value(&mut self.new_vault_dir, i, dir);
+                }
+              });
+          });
+        }
 
         let pass_response = ui.add(
-          egui::TextEdit::singleline(&mut self.master_password)
+          egui::TextEdit::singleline(&mut *self.master_password)
             .password(true)
             .desired_width(ui.available_width())
             .hint_text("Choose a strong master password"),
@@ -37,7 +62,7 @@ impl PixelVaultApp {
           self.attempt_create_vault();
         }
         let pass_confirm_response = ui.add(
-          egui::TextEdit::singleline(&mut self.master_password_confirm)
+          egui::TextEdit::singleline(&mut *self.master_password_confirm)
             .password(true)
             .desired_width(ui.available_width())
             .hint_text("Confirm your master password"),
@@ -46,6 +71,51 @@ impl PixelVaultApp {
         {
           self.attempt_create_vault();
         }
+        self.show_key_file_picker(ui, true);
+        ui.checkbox(&mut self.recovery_key_opt_in, "Make a recovery key")
+          .on_hover_text("A code that opens the vault if the master password is lost, shown once");
+
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+          ui.label("Cipher:");
+          egui::ComboBox::from_id_salt("new_vault_cipher")
+            .selected_text(self.new_vault_cipher.name())
+            .show_ui(ui, |ui| {
+              for cipher in CipherKind::ALL {
+                ui.selectable_value(&mut self.new_vault_cipher, cipher, cipher.name());
+              }
+            });
+        });
+
+        // Key derivation costs for this machine
+        self.poll_kdf_calibration();
+        ui.horizontal(|ui| {
+          ui.label("Key derivation:");
+          if self.is_calibrating_kdf() {
+            ui.spinner();
+            ui.label("Calibrating...");
+          } else {
+            match &self.new_vault_kdf {
+              Some(calibration) => {
+                let params = &calibration.params;
+                ui.label(format!(
+                  "{:?}, {} MiB, {} passes (~{} ms to unlock)",
+                  params.algorithm,
+                  params.memory_kib / 1024,
+                  params.iterations,
+                  calibration.unlock_time.as_millis(),
+                ));
+              }
+              None => {
+                ui.label("Defaults");
+              }
+            }
+            if ui.small_button("⟳").on_hover_text("Recalibrate").clicked() {
+              self.start_kdf_calibration();
+            }
+          }
+        });
 
         ui.add_space(10.0);
 
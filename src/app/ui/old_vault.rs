@@ -1,4 +1,4 @@
-use crate::app::PixelVaultApp;
+use crate::{app::PixelVaultApp, error::VaultError};
 use eframe::egui;
 
 impl PixelVaultApp {
@@ -13,21 +13,37 @@ impl PixelVaultApp {
       PixelVaultApp::fancy_frame(ui).show(ui, |ui| {
         ui.set_width(ui.available_width());
 
-        if let Some(vault_name) = &self.get_selected_vault() {
-          let display_name = vault_name
-            .trim_start_matches("vaults\\")
-            .trim_start_matches("vaults/")
-            .trim_end_matches(".json");
+        if let Some(vault_path) = self.get_selected_vault().clone() {
+          let display_name = self.vault_display_name(&vault_path);
           ui.label(format!("Vault: {}", display_name));
+          if let Some(metadata) = self.get_vault_metadata(&vault_path)
+            && !metadata.description.is_empty()
+          {
+            ui.label(egui::RichText::new(&metadata.description).weak());
+          }
           ui.add_space(10.0);
         }
-        ui.label("Enter the correct master password");
-        let response = ui.add(
-          egui::TextEdit::singleline(&mut self.master_password)
-            .password(true)
-            .desired_width(ui.available_width())
-            .hint_text("Master password"),
-        );
+        let response = if self.use_recovery_key {
+          ui.label("Enter the recovery key made with this vault");
+          ui.add(
+            egui::TextEdit::singleline(&mut *self.recovery_key_input)
+              .font(egui::TextStyle::Monospace)
+              .desired_width(ui.available_width())
+              .hint_text("XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX"),
+          )
+        } else {
+          ui.label("Enter the correct master password");
+          let response = ui.add(
+            egui::TextEdit::singleline(&mut *self.master_password)
+              .password(true)
+              .desired_width(ui.available_width())
+              .hint_text("Master password"),
+          );
+          self.show_key_file_picker(ui, false);
+          response
+        };
+        ui.checkbox(&mut self.use_recovery_key, "Forgot the master password? Use the recovery key")
+          .on_hover_text("You will have to choose a new master password after unlocking");
         if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
           match self.attempt_unlock() {
             Ok(m) => {
@@ -54,6 +70,12 @@ impl PixelVaultApp {
           };
         }
 
+        if let Some(error) = &self.unlock_error {
+          ui.add_space(10.0);
+          ui.colored_label(egui::Color32::RED, error.to_string());
+          ui.label(egui::RichText::new(unlock_error_hint(error)).weak());
+        }
+
         ui.add_space(10.0);
 
         if ui.button("Back to Vaults").clicked() {
@@ -63,3 +85,28 @@ impl PixelVaultApp {
     });
   }
 }
+
+/// What the user can do about a failed unlock
+fn unlock_error_hint(error: &VaultError) -> &'static str {
+  match error {
+    VaultError::Io(_) => "Check that the vault file still exists and can be read.",
+    VaultError::Malformed(_) => {
+      "The file is damaged. Restore one of its backups from the vault list."
+    }
+    VaultError::UnsupportedVersion(_) => "Update PixelVault to open this vault.",
+    VaultError::AuthenticationFailed => {
+      "Check the master password, and the key file if the vault uses one."
+    }
+    VaultError::DataTampered => {
+      "The vault was damaged or modified outside PixelVault. Restore a backup you trust."
+    }
+    VaultError::Schema(_) => {
+      "The password is right, but the contents are damaged. Restore a backup."
+    }
+    VaultError::KeyFileRequired => "Select the key file made with this vault.",
+    VaultError::KeyFile(_) => "Check that the key file is plugged in and readable.",
+    VaultError::NoRecoveryKey => "Unlock with the master password, then make one under Manage Access.",
+    VaultError::RecoveryKeyRejected => "Check the recovery key, dashes and letter case don't matter.",
+    VaultError::Crypto(_) => "Try again, or restart PixelVault.",
+  }
+}
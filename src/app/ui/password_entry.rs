@@ -1,16 +1,19 @@
 use eframe::egui;
 use crate::app::PixelVaultApp;
+use crate::domain;
 use crate::models::{PasswordEntry};
+use zeroize::Zeroizing;
 
 impl PixelVaultApp {
   /// UI for depicting one password entry. 
-  /// Allows for deletion, click to copy, showing/hiding passwords.
-  pub fn show_password_entry(&mut self, ui: &mut egui::Ui, entry: &PasswordEntry, index: usize) {
+  /// Allows for editing, deletion, click to copy, showing/hiding passwords,
+  /// and opening the entry's websites.
+  pub fn show_password_entry(&mut self, ui: &mut egui::Ui, entry: &PasswordEntry) {
     PixelVaultApp::fancy_frame(ui).show(ui, |ui| {
       ui.set_width(ui.available_width());
 
       // Delete confirmation
-      if self.delete_confirmation_index == Some(index) {
+      if self.delete_confirmation_id == Some(entry.id) {
         ui.add_space(5.0);
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
           ui.colored_label(egui::Color32::RED, "⚠ Delete this password?");
@@ -19,10 +22,10 @@ impl PixelVaultApp {
         ui.horizontal(|ui| {
           ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             if ui.button("Cancel").clicked() {
-              self.delete_confirmation_index = None
+              self.delete_confirmation_id = None
             }
             if ui.button("Yes, delete").clicked() {
-              self.delete_entry(index);
+              self.delete_entry(entry.id);
             }
           });
         });
@@ -33,12 +36,19 @@ impl PixelVaultApp {
       // Header row
       ui.columns_const(|[col1, col2]| {
         col1.horizontal(|ui| {
-          ui.label(format!("🌐 {}", entry.service));
+          ui.label(format!("🌐 {}", entry.service)).on_hover_text(format!(
+            "Created {}\nModified {}",
+            PixelVaultApp::format_local_time(&entry.created_at),
+            PixelVaultApp::format_local_time(&entry.modified_at),
+          ));
         });
         col2.horizontal(|ui| {
           ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
             if ui.button("Delete").clicked() {
-              self.delete_confirmation_index = Some(index);
+              self.delete_confirmation_id = Some(entry.id);
+            }
+            if ui.button("Edit").clicked() {
+              self.go_to_edit_entry(entry.id);
             }
           });
         });
@@ -53,23 +63,27 @@ impl PixelVaultApp {
 
       response.on_hover_text("Click to copy username");
 
+      if !entry.urls.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+          ui.label("🔗");
+          for url in &entry.urls {
+            let host = domain::host(url).unwrap_or_else(|| url.clone());
+            ui.hyperlink_to(host, url);
+          }
+        });
+      }
+
       // Password row
       ui.columns_const(|[col1, col2]| {
         col1.horizontal(|ui| {
-          let password = {
-            let vault = match self.vault.as_ref() {
-              Some(v) => v,
-              None => return,
-            };
-            &vault.entries[index].password
-          };
+          let password = &entry.password;
           
-          if Some(index) == self.show_password_index {
+          if Some(entry.id) == self.show_password_id {
             let response =
               ui.add(egui::Label::new(format!("🔑 {}", password)).sense(egui::Sense::click()));
             if response.clicked() {
-              ui.ctx().copy_text(password.clone());
-              self.show_info("Password copied!")
+              let password = Zeroizing::new(password.clone());
+              self.copy_secret(&password, "Password");
             }
             response.on_hover_text("Click to copy");
           } else {
@@ -78,8 +92,8 @@ impl PixelVaultApp {
               ui.add(egui::Label::new("🔑 ••••••••••••••").sense(egui::Sense::click()));
 
             if response.clicked() {
-              ui.ctx().copy_text(password.clone());
-              self.show_info("Password copied!")
+              let password = Zeroizing::new(password.clone());
+              self.copy_secret(&password, "Password");
             }
 
             response.on_hover_text("Click to copy password");
@@ -88,19 +102,61 @@ impl PixelVaultApp {
 
         col2.horizontal(|ui| {
           ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
-            let (button_text, new_pass_idx) = if Some(index) == self.show_password_index {
+            let (button_text, new_shown_id) = if Some(entry.id) == self.show_password_id {
               ("Hide", None)
             } else {
-              ("Show", Some(index))
+              ("Show", Some(entry.id))
             };
 
             if ui.button(button_text).clicked() {
-              self.show_password_index = new_pass_idx;
+              self.show_password_id = new_shown_id;
             }
           });
         });
       });
+
+      if !entry.history.is_empty() {
+        self.show_password_history(ui, entry);
+      }
     });
     ui.add_space(5.0);
   }
+
+  /// Collapsible list of an entry's old passwords, newest first, each
+  /// with copy and restore buttons. Shown in plain text along with the password.
+  fn show_password_history(&mut self, ui: &mut egui::Ui, entry: &PasswordEntry) {
+    let shown = Some(entry.id) == self.show_password_id;
+    let mut restore_index = None;
+    egui::CollapsingHeader::new(format!("History ({})", entry.history.len()))
+      .id_salt(("password_history", entry.id))
+      .show(ui, |ui| {
+        for (i, item) in entry.history.iter().enumerate().rev() {
+          ui.horizontal(|ui| {
+            let retired = PixelVaultApp::format_local_time(&item.retired_at);
+            ui.label(format!("🕒 {}", retired))
+              .on_hover_text("When this password was replaced");
+            if shown {
+              ui.label(&item.password);
+            } else {
+              ui.label("••••••••••••••");
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+              if ui.small_button("Restore").on_hover_text("Restore this password").clicked() {
+                restore_index = Some(i);
+              }
+              if ui.small_button("Copy").clicked() {
+                let password = Zeroizing::new(item.password.clone());
+                self.copy_secret(&password, "Old password");
+              }
+            });
+          });
+        }
+      });
+    if let Some(i) = restore_index {
+      match self.restore_password(entry.id, i) {
+        Ok(_) => self.show_success("Password restored"),
+        Err(e) => self.show_error(e),
+      }
+    }
+  }
 } 
\ No newline at end of file
@@ -0,0 +1,20 @@
+use crate::{app::{PixelVaultApp, app::MIN_MASTER_PASSWORD_SCORE}, pw_gen::PasswordGenerator};
+use eframe::egui;
+
+impl PixelVaultApp {
+  /// Static helper showing the zxcvbn score of a password that opens the
+  /// vault, in red when it is below [`MIN_MASTER_PASSWORD_SCORE`]
+  pub fn show_password_strength(ui: &mut egui::Ui, password: &str) {
+    let score = PasswordGenerator::get_password_score(password);
+    ui.horizontal(|ui| {
+      ui.label("Password Strength:");
+      let text = format!("{}/4", score);
+      if score < MIN_MASTER_PASSWORD_SCORE {
+        ui.colored_label(egui::Color32::RED, text)
+          .on_hover_text(format!("At least {}/4 is required", MIN_MASTER_PASSWORD_SCORE));
+      } else {
+        ui.label(text);
+      }
+    });
+  }
+}
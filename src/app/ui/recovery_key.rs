@@ -0,0 +1,69 @@
+use crate::app::PixelVaultApp;
+use eframe::egui;
+use zeroize::Zeroizing;
+
+impl PixelVaultApp {
+  /// Modal showing a recovery key once, right after it was made
+  pub fn show_new_recovery_key(&mut self, ctx: &egui::Context) {
+    let Some(recovery_key) = self.new_recovery_key.clone() else {
+      return;
+    };
+    let mut save_clicked = false;
+    let mut done_clicked = false;
+
+    egui::Modal::new(egui::Id::new("new_recovery_key")).show(ctx, |ui| {
+      ui.heading("🛟 Recovery Key");
+      ui.label("This key opens the vault if the master password is lost.");
+      ui.label("It is only shown now. Print it or write it down, and keep it somewhere safe.");
+      ui.add_space(10.0);
+      ui.vertical_centered(|ui| {
+        ui.label(egui::RichText::new(recovery_key.as_str()).monospace().size(18.0).strong());
+      });
+      ui.add_space(10.0);
+
+      if ui.button("Save for Printing...").on_hover_text("Save as a text file to print").clicked() {
+        save_clicked = true;
+      }
+      ui.checkbox(&mut self.recovery_key_written_down, "I have printed or written down this key");
+      ui.add_space(10.0);
+      if ui
+        .add_enabled(self.recovery_key_written_down, egui::Button::new("Done"))
+        .clicked()
+      {
+        done_clicked = true;
+      }
+    });
+
+    if save_clicked {
+      self.save_recovery_key(&recovery_key);
+    }
+    if done_clicked {
+      self.new_recovery_key = None;
+      self.recovery_key_written_down = false;
+    }
+  }
+
+  /// Asks where to save the recovery key as a text file, to print it
+  fn save_recovery_key(&mut self, recovery_key: &str) {
+    let Some(path) = rfd::FileDialog::new()
+      .set_title("Save recovery key")
+      .set_file_name("pixelvault-recovery-key.txt")
+      .save_file()
+    else {
+      return;
+    };
+    let name = self
+      .get_current_vault()
+      .and_then(|v| v.metadata.as_ref())
+      .map(|m| m.name.clone())
+      .unwrap_or_default();
+    let text = Zeroizing::new(format!(
+      "PixelVault recovery key for \"{}\"\n\n{}\n\nEnter it on the unlock screen if the master password is lost.\n",
+      name, recovery_key
+    ));
+    match std::fs::write(&path, text.as_bytes()) {
+      Ok(_) => self.show_warning("Recovery key saved. Delete the file once it is printed"),
+      Err(e) => self.show_error(format!("Recovery key couldn't be saved: {}", e)),
+    }
+  }
+}
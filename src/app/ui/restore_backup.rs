@@ -0,0 +1,47 @@
+use crate::app::PixelVaultApp;
+use eframe::egui;
+
+impl PixelVaultApp {
+  /// UI for confirming the master password of a backup before restoring it
+  pub fn show_restore_backup(&mut self, ctx: &egui::Context) {
+    egui::TopBottomPanel::top("top_bar").show(ctx, |ui| {
+      ui.horizontal(|ui| {
+        ui.heading("🔒 PixelVault");
+      });
+    });
+    egui::CentralPanel::default().show(ctx, |ui| {
+      PixelVaultApp::fancy_frame(ui).show(ui, |ui| {
+        ui.set_width(ui.available_width());
+
+        if let Some(backup) = &self.restore_backup {
+          let created = PixelVaultApp::format_local_time_precise(&backup.created);
+          ui.label(format!("Restore backup from {}", created));
+          ui.label("This replaces the current vault, which is kept as a backup.");
+          ui.add_space(10.0);
+        }
+        ui.label("Enter the master password of the backup");
+        let response = ui.add(
+          egui::TextEdit::singleline(&mut *self.master_password)
+            .password(true)
+            .desired_width(ui.available_width())
+            .hint_text("Master password"),
+        );
+        let enter_pressed = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        self.show_key_file_picker(ui, false);
+
+        if ui.button("Restore").clicked() || enter_pressed {
+          match self.attempt_restore_backup() {
+            Ok(m) => self.show_success(m),
+            Err(e) => self.show_error(e),
+          };
+        }
+
+        ui.add_space(10.0);
+
+        if ui.button("Back to Vaults").clicked() {
+          self.back_to_vaults();
+        }
+      });
+    });
+  }
+}
@@ -1,4 +1,4 @@
-use crate::app::PixelVaultApp;
+use crate::{app::PixelVaultApp, vault};
 use eframe::egui;
 
 impl PixelVaultApp {
@@ -33,17 +33,17 @@ impl PixelVaultApp {
         } else {
           for vault_ref in available_vaults {
             let vault_path = vault_ref.clone();
-            let display_name = vault_path
-              .trim_start_matches("vaults\\")
-              .trim_start_matches("vaults/")
-              .trim_end_matches(".json");
+            let display_name = self.vault_display_name(&vault_path);
+            let details = self.vault_details(&vault_path);
 
             let mut select_clicked = false;
             let mut delete_clicked = false;
+            let mut forget_clicked = false;
+            let mut backups_clicked = false;
 
             ui.columns_const(|[col1, col2]| {
               col1.horizontal(|ui| {
-                if ui.button(display_name).clicked() {
+                if ui.button(&display_name).on_hover_text(&details).clicked() {
                   select_clicked = true;
                 }
               });
@@ -52,28 +52,51 @@ impl PixelVaultApp {
                   if ui.small_button("🗑").on_hover_text("Delete vault").clicked() {
                     delete_clicked = true;
                   }
+                  if ui.small_button("🕒").on_hover_text("Show backups").clicked() {
+                    backups_clicked = true;
+                  }
+                  if self.is_registered_vault_file(&vault_path)
+                    && ui.small_button("✖").on_hover_text("Remove from list").clicked()
+                  {
+                    forget_clicked = true;
+                  }
                 });
               });
             });
+            if backups_clicked {
+              self.backups_shown_for = match self.backups_shown_for {
+                Some(ref shown) if *shown == vault_path => None,
+                _ => Some(vault_path.clone()),
+              };
+            }
+            if self.backups_shown_for.as_ref() == Some(&vault_path) {
+              self.show_vault_backups(ui, &vault_path);
+            }
             if select_clicked {
               match self.select_existing_vault(vault_path.clone()) {
+                Ok(_) if self.lock_conflict.is_some() => {}
                 Ok(_) => self.show_info(format!("Vault selected: {}", display_name)),
                 Err(e) => self.show_error(format!("Vault failed to load: {}", e)),
               }
             }
+            if forget_clicked {
+              self.forget_vault_file(&vault_path);
+            }
             if delete_clicked {
-              if let Err(e) = self.delete_vault(&vault_path) {
-                self.show_error(format!("Failed to delete vault: {}", e));
-              } else {
-                self.show_info(format!("Deleted vault '{}'", display_name));
-                self.reload_available_vaults();
-              }
+              self.delete_vault_confirmation = Some(vault_path.clone());
             }
           }
         }
 
         ui.separator();
 
+        self.show_vault_locations(ui);
+        self.show_auto_lock_settings(ui);
+        self.show_password_settings(ui);
+        self.show_backup_settings(ui);
+
+        ui.separator();
+
         if ui.button("➕ Create New Vault").clicked() {
           // let vault_name = format!("vault_{}", chrono::Utc::now().timestamp());
           // let path = format!("vaults/{}.json", vault_name);
@@ -82,5 +105,249 @@ impl PixelVaultApp {
         }
       });
     });
+    self.show_lock_conflict(ctx);
+    self.show_delete_vault_confirmation(ctx);
+  }
+
+  /// Asks before deleting a vault, since its backups go with it
+  fn show_delete_vault_confirmation(&mut self, ctx: &egui::Context) {
+    let Some(vault_path) = self.delete_vault_confirmation.clone() else {
+      return;
+    };
+    let display_name = self.vault_display_name(&vault_path);
+    let backup_count = vault::list_backups(&vault_path).len();
+
+    egui::Modal::new(egui::Id::new("delete_vault")).show(ctx, |ui| {
+      ui.heading("Delete vault?");
+      ui.label(format!("'{}' and its {} backups will be deleted.", display_name, backup_count));
+      ui.colored_label(egui::Color32::RED, "⚠ This can't be undone.");
+      ui.add_space(10.0);
+      ui.horizontal(|ui| {
+        if ui.button("Yes, delete").clicked() {
+          self.delete_vault_confirmation = None;
+          if let Err(e) = self.delete_vault(&vault_path) {
+            self.show_error(format!("Failed to delete vault: {}", e));
+          } else {
+            self.show_info(format!("Deleted vault '{}'", display_name));
+            self.reload_available_vaults();
+          }
+        }
+        if ui.button("Cancel").clicked() {
+          self.delete_vault_confirmation = None;
+        }
+      });
+    });
+  }
+
+  /// Prompt shown when the selected vault is locked by another instance
+  fn show_lock_conflict(&mut self, ctx: &egui::Context) {
+    let Some((_, owner)) = &self.lock_conflict else {
+      return;
+    };
+    let since = PixelVaultApp::format_local_time(&owner.acquired);
+    let message = if owner.is_known() {
+      format!(
+        "This vault is open elsewhere (PID {} on {}, since {}).",
+        owner.pid, owner.host, since
+      )
+    } else {
+      format!("This vault's lock file can't be read (since {}), it may be opening elsewhere.", since)
+    };
+
+    egui::Modal::new(egui::Id::new("lock_conflict")).show(ctx, |ui| {
+      ui.heading("Vault is open elsewhere");
+      ui.label(message);
+      ui.label("Open it read-only? Changes can't be saved.");
+      ui.add_space(10.0);
+      ui.horizontal(|ui| {
+        let mut result = Ok(());
+        if ui.button("Open read-only").clicked() {
+          result = self.open_conflicted_read_only();
+        }
+        if ui
+          .button("Take over")
+          .on_hover_text("Only if PixelVault isn't really running there")
+          .clicked()
+        {
+          result = self.take_over_conflicted_lock();
+        }
+        if ui.button("Cancel").clicked() {
+          self.lock_conflict = None;
+        }
+        if let Err(e) = result {
+          self.show_error(format!("Vault failed to load: {}", e));
+        }
+      });
+    });
+  }
+
+  /// Hover text for a vault in the list: its path, then any metadata
+  fn vault_details(&self, path: &str) -> String {
+    let Some(metadata) = self.get_vault_metadata(path) else {
+      return path.to_string();
+    };
+    let mut details = path.to_string();
+    if !metadata.description.is_empty() {
+      details.push_str(&format!("\n{}", metadata.description));
+    }
+    details.push_str(&format!(
+      "\n{} entries\nCreated {}\nModified {}",
+      metadata.entry_count,
+      PixelVaultApp::format_local_time(&metadata.created),
+      PixelVaultApp::format_local_time(&metadata.modified),
+    ));
+    details
+  }
+
+  /// Collapsible list of watched vault directories, with forms to add
+  /// a directory or register a single vault file
+  fn show_vault_locations(&mut self, ui: &mut egui::Ui) {
+    egui::CollapsingHeader::new("Vault Locations").show(ui, |ui| {
+      let mut remove_index = None;
+      for (i, dir) in self.settings.vault_dirs.iter().enumerate() {
+        ui.horizontal(|ui| {
+          ui.label(format!("📁 {}", dir));
+          ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+            if ui.small_button("✖").on_hover_text("Stop watching directory").clicked() {
+              remove_index = Some(i);
+            }
+          });
+        });
+      }
+      if let Some(i) = remove_index {
+        self.remove_vault_dir(i);
+      }
+
+      ui.horizontal(|ui| {
+        ui.add(
+          egui::TextEdit::singleline(&mut self.vault_dir_input)
+            .hint_text("Directory path")
+            .desired_width(ui.available_width() - 110.0),
+        );
+        if ui.button("Add Directory").clicked()
+          && let Err(e) = self.add_vault_dir()
+        {
+          self.show_error(format!("Failed to add directory: {}", e));
+        }
+      });
+      ui.horizontal(|ui| {
+        ui.add(
+          egui::TextEdit::singleline(&mut self.vault_file_input)
+            .hint_text("Vault file path")
+            .desired_width(ui.available_width() - 110.0),
+        );
+        if ui.button("Open Vault File").clicked()
+          && let Err(e) = self.register_vault_file()
+        {
+          self.show_error(format!("Failed to open vault file: {}", e));
+        }
+      });
+    });
   }
+
+  /// Collapsible form for the auto-lock timeouts
+  fn show_auto_lock_settings(&mut self, ui: &mut egui::Ui) {
+    let mut changed = false;
+    egui::CollapsingHeader::new("Auto-Lock").show(ui, |ui| {
+      let policy = &mut self.settings.auto_lock;
+      ui.horizontal(|ui| {
+        ui.label("Lock after no input for");
+        let mut minutes = policy.idle_secs / 60;
+        let response = ui.add(egui::DragValue::new(&mut minutes).range(0..=240).suffix(" min"));
+        if response.changed() {
+          policy.idle_secs = minutes * 60;
+        }
+        changed |= is_edit_finished(&response);
+      });
+      ui.horizontal(|ui| {
+        ui.label("Lock when minimized or unfocused for");
+        let response = ui.add(
+          egui::DragValue::new(&mut policy.background_secs).range(0..=3600).suffix(" s"),
+        );
+        changed |= is_edit_finished(&response);
+      });
+      ui.label(egui::RichText::new("0 turns a rule off.").weak());
+    });
+    if changed {
+      self.settings_changed();
+    }
+  }
+
+  /// Collapsible form for how long copied passwords stay on the clipboard,
+  /// and how many old passwords each entry keeps
+  fn show_password_settings(&mut self, ui: &mut egui::Ui) {
+    let mut changed = false;
+    egui::CollapsingHeader::new("Passwords").show(ui, |ui| {
+      ui.horizontal(|ui| {
+        ui.label("Clear copied passwords after");
+        let response = ui.add(
+          egui::DragValue::new(&mut self.settings.clipboard_clear_secs)
+            .range(0..=600)
+            .suffix(" s"),
+        );
+        changed |= is_edit_finished(&response);
+      });
+      ui.label(egui::RichText::new("0 keeps them until the vault is locked.").weak());
+      ui.horizontal(|ui| {
+        ui.label("Old passwords kept per entry");
+        let response = ui.add(
+          egui::DragValue::new(&mut self.settings.max_password_history).range(0..=100),
+        );
+        changed |= is_edit_finished(&response);
+      });
+    });
+    if changed {
+      self.settings_changed();
+    }
+  }
+
+  /// Collapsible form for how many timestamped backups each vault keeps
+  fn show_backup_settings(&mut self, ui: &mut egui::Ui) {
+    let mut changed = false;
+    egui::CollapsingHeader::new("Backups").show(ui, |ui| {
+      let policy = &mut self.settings.backup_policy;
+      ui.horizontal(|ui| {
+        ui.label("Keep the last");
+        let response =
+          ui.add(egui::DragValue::new(&mut policy.keep_recent).range(1..=100).suffix(" saves"));
+        changed |= is_edit_finished(&response);
+      });
+      ui.horizontal(|ui| {
+        ui.label("And one save a day for");
+        let response =
+          ui.add(egui::DragValue::new(&mut policy.keep_daily_days).range(0..=365).suffix(" days"));
+        changed |= is_edit_finished(&response);
+      });
+    });
+    if changed {
+      self.settings_changed();
+    }
+  }
+
+  /// Lists the timestamped backups of a vault, each with a restore button
+  fn show_vault_backups(&mut self, ui: &mut egui::Ui, vault_path: &str) {
+    let backups = vault::list_backups(vault_path);
+    ui.indent(vault_path, |ui| {
+      if backups.is_empty() {
+        ui.label("No backups yet.");
+      }
+      for backup in backups {
+        ui.horizontal(|ui| {
+          let created = PixelVaultApp::format_local_time_precise(&backup.created);
+          ui.label(format!("🕒 {}", created));
+          ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+            if ui.small_button("Restore").clicked() {
+              self.go_to_restore_backup(vault_path.to_string(), backup.clone());
+            }
+          });
+        });
+      }
+    });
+  }
+}
+
+/// Whether a drag value was changed and let go of, so settings aren't
+/// written on every frame of a drag
+fn is_edit_finished(response: &egui::Response) -> bool {
+  (response.changed() && !response.dragged()) || response.drag_stopped()
 }
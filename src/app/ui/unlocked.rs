@@ -4,14 +4,28 @@ use crate::app::{PixelVaultApp, app::FeatureState, app::AppState};
 impl PixelVaultApp {  
   /// UI depicting an unlocked vault.
   pub fn show_unlocked(&mut self, ctx: &egui::Context) {
-    
+    self.show_merge_conflicts(ctx);
+    self.show_new_recovery_key(ctx);
+
     egui::TopBottomPanel::top("top_bar").show(ctx, |ui| {
       ui.horizontal(|ui| {
         ui.heading("🔓 PixelVault");
+        if let Some(metadata) = self.get_current_vault().and_then(|v| v.metadata.as_ref()) {
+          ui.label(&metadata.name);
+        }
+        if self.read_only {
+          ui.label("(read-only)")
+            .on_hover_text("This vault is open in another instance, changes can't be saved");
+        }
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
           if ui.button("🔒 Lock").clicked() {
             self.lock_vault();
           }
+          if let Some(remaining) = self.auto_lock_remaining() {
+            let secs = remaining.as_secs();
+            ui.label(egui::RichText::new(format!("🕒 {}:{:02}", secs / 60, secs % 60)).weak())
+              .on_hover_text("Time until the vault locks itself");
+          }
         });
       });
     });
@@ -22,11 +36,26 @@ impl PixelVaultApp {
         // First, determine which state we're in
         let is_new_entry = matches!(&self.state(), AppState::Unlocked { feature_state } if matches!(feature_state, FeatureState::NewEntry{ .. }));
         let is_edit_entry = matches!(&self.state(), AppState::Unlocked { feature_state } if matches!(feature_state, FeatureState::EditEntry));
+        let is_change_password = matches!(&self.state(), AppState::Unlocked { feature_state } if matches!(feature_state, FeatureState::ChangeMasterPassword));
+        let is_vault_settings = matches!(&self.state(), AppState::Unlocked { feature_state } if matches!(feature_state, FeatureState::VaultSettings));
+        let is_key_slots = matches!(&self.state(), AppState::Unlocked { feature_state } if matches!(feature_state, FeatureState::KeySlots));
         
-        if is_new_entry {
+        if self.must_reset_password && !self.read_only {
+          self.show_change_master_password(ui);
+          return;
+        }
+        if self.read_only {
+          ui.label("This vault is open read-only because another instance has it open.");
+        } else if is_new_entry {
             self.show_new_entry(ui);
         } else if is_edit_entry {
             self.show_edit_entry(ui);
+        } else if is_change_password {
+            self.show_change_master_password(ui);
+        } else if is_vault_settings {
+            self.show_vault_settings(ui);
+        } else if is_key_slots {
+            self.show_key_slots(ui);
         }
         // if let AppState::Unlocked { feature_state } = &mut self.state_mut() {
         //   match feature_state {
@@ -67,11 +96,11 @@ impl PixelVaultApp {
 
         if let Some(vault) = self.get_current_vault() {
           let mut results = vault.search_entries(&self.search_query);
-          results.sort_by(|a, b| b.2.cmp(&a.2)); // sort by score
+          results.sort_by_key(|r| std::cmp::Reverse(r.1)); // sort by score
           
-          // Make results only have references to Password Entry
+          // Clone the entries so the vault can be changed while listing them
           let results: Vec<_> = results.into_iter()
-            .map(|(i, entry, score)| (i, entry.clone(), score))
+            .map(|(entry, score)| (entry.clone(), score))
             .collect();
           
           if results.is_empty() {
@@ -88,8 +117,8 @@ impl PixelVaultApp {
             egui::ScrollArea::vertical()
               .auto_shrink(false)
               .show(ui, |ui| {
-                for (i, entry, _score) in results {
-                  self.show_password_entry(ui, &entry, i);
+                for (entry, _score) in results {
+                  self.show_password_entry(ui, &entry);
                 }
               });
           }
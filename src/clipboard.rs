@@ -0,0 +1,87 @@
+//! The `clipboard` module copies secrets to the system clipboard and clears them again.
+//!
+//! Responsibilities:
+//! - Put a password on the clipboard, remembering when to clear it.
+//! - Clear it once the delay runs out, on lock, and on exit.
+//!
+//! A secret is only cleared while the clipboard still holds it, so anything
+//! the user copied afterwards is left alone.
+use std::{
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+use zeroize::Zeroizing;
+
+/// When the copied secret gets cleared, shared with the UI for its countdown.
+/// `None` once nothing is waiting to be cleared.
+pub type ClearDeadline = Arc<Mutex<Option<Instant>>>;
+
+/// System clipboard that clears the secrets copied through it
+#[derive(Default)]
+pub struct SecretClipboard {
+  /// System clipboard, opened on first use
+  clipboard: Option<arboard::Clipboard>,
+  /// Last secret copied, until it is cleared
+  copied: Option<Zeroizing<String>>,
+  /// When `copied` is cleared, `None` if it stays until lock or exit
+  clear_at: ClearDeadline,
+}
+
+impl SecretClipboard {
+  /// Copies `secret`, to be cleared after `clear_after` (or on lock or exit)
+  ///
+  /// # Errors
+  /// Returns an error if the system clipboard can't be opened or written.
+  pub fn copy(&mut self, secret: &str, clear_after: Option<Duration>) -> Result<(), String> {
+    let clipboard = match &mut self.clipboard {
+      Some(clipboard) => clipboard,
+      None => self.clipboard.insert(arboard::Clipboard::new().map_err(|e| e.to_string())?),
+    };
+    clipboard.set_text(secret).map_err(|e| e.to_string())?;
+    self.copied = Some(Zeroizing::new(secret.to_string()));
+    self.set_deadline(clear_after.map(|delay| Instant::now() + delay));
+    Ok(())
+  }
+
+  /// Shared deadline of the copied secret, for showing a countdown
+  pub fn deadline(&self) -> ClearDeadline {
+    Arc::clone(&self.clear_at)
+  }
+
+  /// Time left before the copied secret is cleared
+  pub fn remaining(&self) -> Option<Duration> {
+    self
+      .clear_at
+      .lock()
+      .ok()
+      .and_then(|deadline| *deadline)
+      .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+  }
+
+  /// Clears the copied secret if its delay has run out
+  pub fn clear_if_due(&mut self) {
+    if self.remaining().is_some_and(|remaining| remaining.is_zero()) {
+      self.clear();
+    }
+  }
+
+  /// Clears the copied secret now, if the clipboard still holds it
+  pub fn clear(&mut self) {
+    self.set_deadline(None);
+    let Some(secret) = self.copied.take() else {
+      return;
+    };
+    if let Some(clipboard) = &mut self.clipboard {
+      let current = clipboard.get_text().map(Zeroizing::new);
+      if current.is_ok_and(|current| *current == *secret) {
+        clipboard.clear().ok();
+      }
+    }
+  }
+
+  fn set_deadline(&self, deadline: Option<Instant>) {
+    if let Ok(mut clear_at) = self.clear_at.lock() {
+      *clear_at = deadline;
+    }
+  }
+}
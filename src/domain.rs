@@ -0,0 +1,130 @@
+//! The `domain` module normalizes website URLs and matches them by site.
+//!
+//! Responsibilities:
+//! - Turn what the user typed into a full URL (scheme, lowercase host).
+//! - Find the registrable domain (eTLD+1) of a host, so `login.example.com`
+//!   and `www.example.com` both count as `example.com`.
+//!
+//! Site matching uses the Public Suffix List bundled in
+//! `data/public_suffix_list.dat`, so `shop.co.uk` and `me.github.io` are
+//! sites of their own rather than parts of `co.uk` or `github.io`.
+use publicsuffix::{List, Psl};
+use std::net::IpAddr;
+use std::sync::LazyLock;
+use url::Url;
+
+/// The bundled Public Suffix List, parsed on first use
+static PUBLIC_SUFFIXES: LazyLock<List> = LazyLock::new(|| {
+  include_str!("../data/public_suffix_list.dat")
+    .parse()
+    .expect("bundled public suffix list should parse")
+});
+
+/// Parses `input` as a URL, assuming `https://` when no scheme is given
+fn parse(input: &str) -> Option<Url> {
+  let input = input.trim();
+  if input.is_empty() || input.contains(char::is_whitespace) {
+    return None;
+  }
+  let url = if input.contains("://") {
+    Url::parse(input).ok()?
+  } else {
+    Url::parse(&format!("https://{input}")).ok()?
+  };
+  url.host_str().is_some_and(|host| !host.is_empty()).then_some(url)
+}
+
+/// Normalizes a website address for storing on an entry.
+///
+/// Adds `https://` when the scheme is missing and lowercases the host,
+/// e.g. `Login.Example.com/x` becomes `https://login.example.com/x`.
+///
+/// # Errors
+/// Returns an error if `input` isn't a URL with a host.
+pub fn normalize_url(input: &str) -> Result<String, String> {
+  parse(input)
+    .map(String::from)
+    .ok_or_else(|| format!("\"{}\" is not a valid website address", input.trim()))
+}
+
+/// Normalizes one URL per line, skipping blank lines
+///
+/// # Errors
+/// Returns the error of the first line that isn't a valid URL.
+pub fn normalize_url_list(text: &str) -> Result<Vec<String>, String> {
+  text
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(normalize_url)
+    .collect()
+}
+
+/// Lowercase host of a URL or bare host name, `None` if `input` isn't one.
+/// Bare words without a dot (like `example`) don't count as hosts.
+pub fn host(input: &str) -> Option<String> {
+  let url = parse(input)?;
+  let host = url.host_str()?.trim_end_matches('.').to_string();
+  let is_host = host.contains('.') || host == "localhost" || host.starts_with('[');
+  is_host.then_some(host)
+}
+
+/// Registrable domain (eTLD+1) of `host`, e.g. `example.co.uk` for
+/// `login.example.co.uk`. IP addresses, and hosts that are themselves a
+/// public suffix, are returned unchanged.
+pub fn registrable_domain(host: &str) -> String {
+  let host = host.trim_end_matches('.').to_lowercase();
+  if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+    return host;
+  }
+
+  PUBLIC_SUFFIXES
+    .domain(host.as_bytes())
+    .and_then(|domain| std::str::from_utf8(domain.as_bytes()).ok().map(str::to_string))
+    .unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn urls_get_a_scheme_and_a_lowercase_host() {
+    assert_eq!(
+      normalize_url(" Login.Example.com/x ").unwrap(),
+      "https://login.example.com/x"
+    );
+    assert_eq!(normalize_url("http://example.com").unwrap(), "http://example.com/");
+    assert!(normalize_url("not a url").is_err());
+    assert_eq!(
+      normalize_url_list("example.com\n\n  \nexample.org").unwrap(),
+      vec!["https://example.com/", "https://example.org/"]
+    );
+  }
+
+  #[test]
+  fn bare_words_are_not_hosts() {
+    assert_eq!(host("example"), None);
+    assert_eq!(host("https://Www.Example.com/login").as_deref(), Some("www.example.com"));
+    assert_eq!(host("localhost").as_deref(), Some("localhost"));
+  }
+
+  #[test]
+  fn subdomains_share_a_registrable_domain() {
+    assert_eq!(registrable_domain("login.example.com"), "example.com");
+    assert_eq!(registrable_domain("www.example.com."), "example.com");
+    assert_eq!(registrable_domain("a.shop.co.uk"), "shop.co.uk");
+  }
+
+  #[test]
+  fn sites_under_a_public_suffix_stay_apart() {
+    assert_ne!(registrable_domain("evil.co.il"), registrable_domain("bank.co.il"));
+    assert_eq!(registrable_domain("me.github.io"), "me.github.io");
+    assert_eq!(registrable_domain("github.io"), "github.io");
+  }
+
+  #[test]
+  fn ip_addresses_are_kept_whole() {
+    assert_eq!(registrable_domain("192.168.1.10"), "192.168.1.10");
+    assert_eq!(registrable_domain("[::1]"), "[::1]");
+  }
+}
@@ -0,0 +1,75 @@
+//! The `error` module defines the errors of loading, unlocking and saving vaults.
+//!
+//! `krypt` and `vault` return [`VaultError`], so the UI can tell a wrong
+//! password apart from a missing or corrupted file.
+use std::{fmt, io};
+
+/// Why a vault couldn't be read, decrypted or written
+#[derive(Debug)]
+pub enum VaultError {
+  /// Reading or writing a file failed
+  Io(io::Error),
+  /// The file isn't a vault, or is truncated
+  Malformed(String),
+  /// The header has a format version newer than this build understands
+  UnsupportedVersion(u32),
+  /// The key didn't decrypt the vault, most likely a wrong password
+  AuthenticationFailed,
+  /// The key is right, but the encrypted data or the header was changed
+  /// or damaged after encryption
+  DataTampered,
+  /// The vault decrypted, but isn't a valid vault inside (or can't be serialized)
+  Schema(String),
+  /// The vault needs a key file and none was given
+  KeyFileRequired,
+  /// The key file couldn't be read or created
+  KeyFile(String),
+  /// A recovery key was given, but the vault doesn't have one
+  NoRecoveryKey,
+  /// The recovery key didn't open the vault's recovery slot
+  RecoveryKeyRejected,
+  /// Key derivation or encryption failed
+  Crypto(String),
+}
+
+impl fmt::Display for VaultError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VaultError::Io(e) if e.kind() == io::ErrorKind::NotFound => {
+        write!(f, "Vault file not found")
+      }
+      VaultError::Io(e) => write!(f, "Vault file couldn't be accessed: {}", e),
+      VaultError::Malformed(e) => write!(f, "Vault file is corrupted: {}", e),
+      VaultError::UnsupportedVersion(v) => write!(
+        f,
+        "Vault format version {} is newer than this version of PixelVault supports",
+        v
+      ),
+      VaultError::AuthenticationFailed => write!(f, "Incorrect master password"),
+      VaultError::DataTampered => {
+        write!(f, "Vault data or header was corrupted or modified after it was saved")
+      }
+      VaultError::Schema(e) => write!(f, "Vault contents are invalid: {}", e),
+      VaultError::KeyFileRequired => write!(f, "This vault needs its key file to unlock"),
+      VaultError::KeyFile(e) => write!(f, "Key file couldn't be used: {}", e),
+      VaultError::NoRecoveryKey => write!(f, "This vault has no recovery key"),
+      VaultError::RecoveryKeyRejected => write!(f, "Incorrect recovery key"),
+      VaultError::Crypto(e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl std::error::Error for VaultError {}
+
+impl From<io::Error> for VaultError {
+  fn from(e: io::Error) -> Self {
+    VaultError::Io(e)
+  }
+}
+
+/// Lets the app's `String` errors `?` vault errors directly
+impl From<VaultError> for String {
+  fn from(e: VaultError) -> Self {
+    e.to_string()
+  }
+}
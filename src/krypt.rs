@@ -1,112 +1,816 @@
 //! The `krypt` module handles cryptography.
 //!
 //! Responsibilities:
-//! - Derive the key from the password and a random salt.
-//! - Decrypt / Encrypt the entire vault using AES-GCM.
+//! - Derive the key from the password and a random salt, plus a key file if
+//!   the vault uses one.
+//! - Decrypt / Encrypt the entire vault using AES-256-GCM or XChaCha20-Poly1305.
 //! 
 //! # Security model
-//! - Master password is never stored (if lost, the vault is locked forever)
-//! - Encryption keys are derived from the master password using Argon2.
+//! - Master password is never stored. If it is lost, the vault can only be
+//!   opened with a recovery key, when one was made (otherwise it is locked forever).
+//! - Vault data is encrypted with a random data key, wrapped in one or more
+//!   key slots (LUKS-style). Each slot key is derived from a password, and
+//!   optionally a key file, using Argon2.
+//! - Slots can be added or removed without re-encrypting the entries. Removing
+//!   a slot doesn't change the data key, so copies of the vault made before
+//!   stay readable to whoever could open that slot.
 //! - Vault data is encrypted as a single item.
-//! - Nonces are regenerated randomly each encryption
+//! - Nonces are regenerated randomly each encryption. XChaCha20-Poly1305's
+//!   192-bit nonces stay safe from collisions over any number of saves.
+//! - The data key is kept in zeroizing memory for the unlocked session,
+//!   slot salts only change together with their password.
+//! - Intermediate plaintext buffers are wiped as soon as they are dropped.
+//! - KDF and cipher parameters are recorded in the vault header, so old
+//!   vaults keep opening after the defaults change.
+//! - The header and unencrypted metadata are authenticated as AES-GCM
+//!   associated data, so editing them makes decryption fail.
 use aes_gcm::{
   Aes256Gcm, 
-  aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore},
+  aead::{Aead, AeadCore, KeyInit, Nonce, OsRng, Payload, rand_core::RngCore},
 };
-use argon2::Argon2;
+use argon2::{Algorithm, Argon2, Params, Version};
+use blake2::{Blake2b, Digest, digest::consts::U32};
+use chacha20poly1305::XChaCha20Poly1305;
+use std::{
+  fs::{File, OpenOptions},
+  io::{Read, Write},
+  ops::Range,
+  time::{Duration, Instant},
+};
+use zeroize::Zeroizing;
+
+use crate::{
+  error::VaultError,
+  models::{
+    CipherKind, EncryptedVault, KdfAlgorithm, KdfParams, KeySlot, PasswordVault,
+    VAULT_FORMAT_VERSION, VaultHeader,
+  },
+};
+
+/// Domain separation for [`KeyFile`] digests
+const KEY_FILE_CONTEXT: &[u8] = b"pixelvault key file";
+
+/// Length of key files made by [`generate_key_file`]
+const KEY_FILE_LEN: usize = 64;
 
-use crate::models::{EncryptedVault, PasswordVault};
+/// Digest of a key file, the second factor some vaults need to unlock.
+///
+/// Any file can be a key file, only its digest is kept in memory.
+#[derive(Clone)]
+pub struct KeyFile {
+  digest: Zeroizing<[u8; 32]>,
+}
+
+impl KeyFile {
+  /// Reads and hashes the key file at `path`
+  ///
+  /// # Errors
+  /// Returns [`VaultError::KeyFile`] if the file can't be read or is empty.
+  pub fn read(path: &str) -> Result<Self, VaultError> {
+    let key_file_error = |e: std::io::Error| VaultError::KeyFile(format!("{}: {}", path, e));
+    let mut data = Zeroizing::new(Vec::new());
+    File::open(path)
+      .and_then(|mut file| file.read_to_end(&mut data))
+      .map_err(key_file_error)?;
+    if data.is_empty() {
+      return Err(VaultError::KeyFile(format!("{} is empty", path)));
+    }
+    Ok(Self::from_bytes(&data))
+  }
+
+  fn from_bytes(data: &[u8]) -> Self {
+    let digest = Blake2b::<U32>::new()
+      .chain_update(KEY_FILE_CONTEXT)
+      .chain_update(data)
+      .finalize();
+    Self { digest: Zeroizing::new(digest.into()) }
+  }
+}
+
+/// Writes a new key file of random bytes to `path`, never overwriting a file.
+///
+/// # Errors
+/// Returns [`VaultError::KeyFile`] if the file exists or can't be written.
+pub fn generate_key_file(path: &str) -> Result<KeyFile, VaultError> {
+  let key_file_error = |e: std::io::Error| VaultError::KeyFile(format!("{}: {}", path, e));
+  let mut data = Zeroizing::new([0u8; KEY_FILE_LEN]);
+  OsRng.fill_bytes(data.as_mut());
+  let mut file = OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(path)
+    .map_err(key_file_error)?;
+  file
+    .write_all(data.as_ref())
+    .and_then(|_| file.sync_all())
+    .map_err(key_file_error)?;
+  Ok(KeyFile::from_bytes(data.as_ref()))
+}
 
 /// Derives a 256-bit encryption key from a master password and a salt.
 /// 
 /// # Arguments 
 /// - `password`: The master password used for key derivation.
 /// - `salt`: A random salt used for key derivation.
+/// - `kdf`: The KDF and cost parameters to derive with.
+/// - `key_file`: Key file mixed in as the Argon2 secret, if the vault uses one.
 /// 
 /// # Returns
 /// A 32-bit key suitable for AES-256-GCM
 /// 
 /// # Errors
-/// Returns an error if key derivation fails.
+/// Returns an error if the parameters are invalid or key derivation fails.
 /// 
 /// # Security
-/// - Argon2id with default parameters.
-/// - Returned key must not be persisted.
-pub fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], String> {
-  let argon2 = Argon2::default();
-  let mut key = [0u8; 32];
+/// - Argon2id with the parameters stored in the vault header.
+/// - Returned key must not be persisted, and is wiped when dropped.
+pub fn derive_key(
+  password: &str,
+  salt: &[u8],
+  kdf: &KdfParams,
+  key_file: Option<&KeyFile>,
+) -> Result<Zeroizing<[u8; 32]>, VaultError> {
+  let argon2 = match kdf.algorithm {
+    KdfAlgorithm::Argon2id => {
+      let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| VaultError::Crypto(format!("Invalid KDF parameters: {}", e)))?;
+      match key_file {
+        Some(key_file) => {
+          Argon2::new_with_secret(key_file.digest.as_ref(), Algorithm::Argon2id, Version::V0x13, params)
+            .map_err(|e| VaultError::Crypto(format!("Invalid key file secret: {}", e)))?
+        }
+        None => Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+      }
+    }
+  };
+  let mut key = Zeroizing::new([0u8; 32]);
 
   argon2
-    .hash_password_into(password.as_bytes(), salt, &mut key)
-    .map_err(|e| format!("Key derivation failed: {}", e.to_string()))?;
+    .hash_password_into(password.as_bytes(), salt, key.as_mut())
+    .map_err(|e| VaultError::Crypto(format!("Key derivation failed: {}", e)))?;
 
   Ok(key)
 }
 
-/// Encrypts plaintext vault using a master password.
+/// Unlock time that [`calibrate_kdf`] aims for on the current machine.
+pub const CALIBRATION_TARGET: Range<Duration> = Duration::from_millis(500)..Duration::from_secs(1);
+
+/// Argon2 memory cost calibration starts at, and never exceeds (64 MiB).
+const CALIBRATION_MAX_MEMORY_KIB: u32 = 64 * 1024;
+/// Argon2 memory cost calibration never goes below (the crate default, 19 MiB).
+const CALIBRATION_MIN_MEMORY_KIB: u32 = Params::DEFAULT_M_COST;
+/// Upper bound on Argon2 passes chosen by calibration.
+const CALIBRATION_MAX_ITERATIONS: u32 = 16;
+
+/// Outcome of [`calibrate_kdf`]
+#[derive(Clone)]
+pub struct KdfCalibration {
+  /// Chosen KDF parameters
+  pub params: KdfParams,
+  /// Measured time of one derivation with `params`
+  pub unlock_time: Duration,
+}
+
+/// Benchmarks Argon2id on this machine and picks costs that land in `target`.
 /// 
-/// Serializes the vault, derives a key, and encrypts the serialized data.
+/// Memory starts at 64 MiB and is halved (stopping at 19 MiB) while a single
+/// pass is already too slow, then the number of passes is scaled to hit the
+/// middle of `target`.
 /// 
 /// # Security
-/// - Encrypts data with AES-256-GCM.
-/// - Nonce is generated using OsRng each time.
+/// - Never returns anything weaker than [`KdfParams::default`] costs.
 /// 
-/// # Errors 
-/// Returns an error if key derivation, encryption, or serialization fails.
-pub fn encrypt_vault(
-  vault: &PasswordVault,
+/// # Errors
+/// Returns an error if key derivation fails.
+pub fn calibrate_kdf(target: Range<Duration>) -> Result<KdfCalibration, VaultError> {
+  let salt = gen_salt();
+  let mut params = KdfParams {
+    memory_kib: CALIBRATION_MAX_MEMORY_KIB,
+    iterations: 1,
+    parallelism: 1,
+    ..KdfParams::default()
+  };
+  let measure = |params: &KdfParams| -> Result<Duration, VaultError> {
+    let start = Instant::now();
+    derive_key("calibration", &salt, params, None)?;
+    Ok(start.elapsed())
+  };
+
+  let mut elapsed = measure(&params)?;
+  while elapsed > target.end && params.memory_kib > CALIBRATION_MIN_MEMORY_KIB {
+    params.memory_kib = (params.memory_kib / 2).max(CALIBRATION_MIN_MEMORY_KIB);
+    elapsed = measure(&params)?;
+  }
+
+  // Time scales roughly linearly with the number of passes
+  let per_pass = elapsed.as_secs_f64() / params.iterations as f64;
+  let middle = (target.start + target.end).as_secs_f64() / 2.0;
+  let passes = (middle / per_pass.max(f64::EPSILON)).round() as u32;
+  params.iterations = passes.clamp(KdfParams::default().iterations, CALIBRATION_MAX_ITERATIONS);
+
+  let mut unlock_time = measure(&params)?;
+  while unlock_time < target.start && params.iterations < CALIBRATION_MAX_ITERATIONS {
+    params.iterations += 1;
+    unlock_time = measure(&params)?;
+  }
+  Ok(KdfCalibration { params, unlock_time })
+}
+
+/// Random key the vault entries are encrypted with, held for an unlocked session.
+/// 
+/// Keeps the key slots it is wrapped in, so the vault can be re-encrypted
+/// and its slots edited without running the KDF for every slot.
+#[derive(Clone)]
+pub struct SessionKey {
+  /// Vault data key
+  key: Zeroizing<[u8; 32]>,
+  /// Every key slot of the vault, written to the header on save
+  slots: Vec<KeySlot>,
+  /// Salt of the slot this session was unlocked with, which identifies it
+  unlocked_salt: [u8; 16],
+  /// Key file of the slot this session was unlocked with, if any
+  key_file: Option<KeyFile>,
+  /// Cipher the vault is encrypted with on save
+  cipher: CipherKind,
+}
+
+impl SessionKey {
+  /// Key slot this session was unlocked with
+  fn unlocked_slot(&self) -> Option<&KeySlot> {
+    self.slots.iter().find(|slot| slot.salt == self.unlocked_salt)
+  }
+
+  /// KDF parameters of the slot this session was unlocked with
+  pub fn kdf(&self) -> KdfParams {
+    self.unlocked_slot().map(|slot| slot.kdf.clone()).unwrap_or_default()
+  }
+
+  /// Cipher the vault is encrypted with on save
+  pub fn cipher(&self) -> CipherKind {
+    self.cipher
+  }
+
+  /// Switches the cipher, the vault is re-encrypted with it on the next save.
+  /// The data key and key slots stay the same.
+  pub fn set_cipher(&mut self, cipher: CipherKind) {
+    self.cipher = cipher;
+  }
+
+  /// Every key slot of the vault
+  pub fn slots(&self) -> &[KeySlot] {
+    &self.slots
+  }
+
+  /// Whether `slot` is the one this session was unlocked with
+  pub fn is_unlocked_slot(&self, slot: &KeySlot) -> bool {
+    slot.salt == self.unlocked_salt
+  }
+
+  /// Checks a password (and key file) against the slot this session was unlocked with
+  pub fn verify_password(&self, password: &str) -> bool {
+    self
+      .unlocked_slot()
+      .is_some_and(|slot| open_slot(slot, password, self.key_file.as_ref()).is_ok())
+  }
+
+  /// Adds a slot that unlocks the vault with `password` and `key_file`
+  /// 
+  /// # Errors
+  /// Returns an error if key derivation or wrapping fails.
+  pub fn add_slot(
+    &mut self,
+    label: &str,
+    password: &str,
+    kdf: &KdfParams,
+    key_file: Option<&KeyFile>,
+  ) -> Result<(), VaultError> {
+    let slot = seal_slot(&self.key, label, password, kdf, key_file)?;
+    self.slots.push(slot);
+    Ok(())
+  }
+
+  /// Removes a slot, so its password no longer unlocks the vault.
+  /// 
+  /// # Errors
+  /// Returns an error for the slot this session was unlocked with.
+  pub fn remove_slot(&mut self, index: usize) -> Result<(), VaultError> {
+    match self.slots.get(index) {
+      Some(slot) if self.is_unlocked_slot(slot) => Err(VaultError::Crypto(
+        "The key slot in use can't be removed, change its password instead".into(),
+      )),
+      Some(_) => {
+        self.slots.remove(index);
+        Ok(())
+      }
+      None => Err(VaultError::Crypto("No such key slot".into())),
+    }
+  }
+
+  /// Replaces the slot this session was unlocked with by one for a new
+  /// password, keeping its label and key file. Other slots are untouched.
+  /// 
+  /// # Errors
+  /// Returns an error if key derivation or wrapping fails.
+  pub fn change_password(&mut self, password: &str, kdf: &KdfParams) -> Result<(), VaultError> {
+    let index = self
+      .slots
+      .iter()
+      .position(|slot| slot.salt == self.unlocked_salt)
+      .ok_or(VaultError::AuthenticationFailed)?;
+    let slot = seal_slot(&self.key, &self.slots[index].label, password, kdf, self.key_file.as_ref())?;
+    self.unlocked_salt = slot.salt;
+    self.slots[index] = slot;
+    Ok(())
+  }
+
+  /// Whether this session was unlocked with a recovery key
+  pub fn unlocked_with_recovery_key(&self) -> bool {
+    self.unlocked_slot().is_some_and(|slot| slot.recovery)
+  }
+
+  /// Whether the vault has a recovery slot
+  pub fn has_recovery_key(&self) -> bool {
+    self.slots.iter().any(|slot| slot.recovery)
+  }
+
+  /// Wraps the data key for `recovery_key`, replacing any earlier recovery slot.
+  /// 
+  /// The code already has full key strength, so the slot uses the default
+  /// KDF costs rather than the calibrated ones.
+  /// 
+  /// # Errors
+  /// Returns an error if key derivation or wrapping fails.
+  pub fn set_recovery_key(&mut self, recovery_key: &str) -> Result<(), VaultError> {
+    let code = normalize_recovery_key(recovery_key);
+    let mut slot = seal_slot(&self.key, RECOVERY_SLOT_LABEL, &code, &KdfParams::default(), None)?;
+    slot.recovery = true;
+    if self.unlocked_with_recovery_key() {
+      self.unlocked_salt = slot.salt;
+    }
+    self.slots.retain(|slot| !slot.recovery);
+    self.slots.push(slot);
+    Ok(())
+  }
+
+  /// Sets a new master password after unlocking with a recovery key.
+  /// 
+  /// Replaces the owner's slot (the first password slot) by one for
+  /// `password` without a key file, since the old factors may be lost,
+  /// keeping its label and KDF costs. The session continues as if unlocked
+  /// with the new slot, and the recovery slot is kept.
+  /// 
+  /// # Errors
+  /// Returns an error if key derivation or wrapping fails.
+  pub fn reset_password(&mut self, password: &str) -> Result<(), VaultError> {
+    let index = self.slots.iter().position(|slot| !slot.recovery);
+    let (label, kdf) = match index {
+      Some(index) => (self.slots[index].label.clone(), self.slots[index].kdf.clone()),
+      None => (OWNER_SLOT_LABEL.to_string(), KdfParams::default()),
+    };
+    let slot = seal_slot(&self.key, &label, password, &kdf, None)?;
+    self.unlocked_salt = slot.salt;
+    self.key_file = None;
+    match index {
+      Some(index) => self.slots[index] = slot,
+      None => self.slots.insert(0, slot),
+    }
+    Ok(())
+  }
+
+  /// Takes the key slots of a copy of the vault saved elsewhere, which
+  /// shares this session's data key. Copies without key slots are ignored.
+  pub fn adopt_slots(&mut self, encrypted: &EncryptedVault) {
+    if !encrypted.header.key_slots.is_empty() {
+      self.slots = encrypted.header.key_slots.clone();
+    }
+  }
+}
+
+/// Label of the first key slot of a vault
+pub const OWNER_SLOT_LABEL: &str = "Owner";
+
+/// Makes a new random data key, wrapped in one slot for the master password.
+/// The vault is encrypted with the default cipher until [`SessionKey::set_cipher`].
+/// 
+/// # Errors
+/// Returns an error if key derivation fails.
+pub fn new_session_key(
   master_password: &str,
-) -> Result<EncryptedVault, String> {
-  let salt: [u8; 16] = gen_salt();
-  let key = derive_key(master_password, &salt)?;
-  let cipher = Aes256Gcm::new_from_slice(&key)
-    .map_err(|e| e.to_string())?;
+  kdf: &KdfParams,
+  key_file: Option<KeyFile>,
+) -> Result<SessionKey, VaultError> {
+  let mut key = Zeroizing::new([0u8; 32]);
+  OsRng.fill_bytes(key.as_mut());
+  let slot = seal_slot(&key, OWNER_SLOT_LABEL, master_password, kdf, key_file.as_ref())?;
+  Ok(SessionKey {
+    key,
+    unlocked_salt: slot.salt,
+    slots: vec![slot],
+    key_file,
+    cipher: CipherKind::default(),
+  })
+}
 
-  // Use OsRng for cryptographic randomness
-  let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-  
-  // Serialize from vault => bytes
-  let serialized = serde_json::to_vec(&vault).map_err(|e| e.to_string())?;
+/// Label of the slot a recovery key opens
+pub const RECOVERY_SLOT_LABEL: &str = "Recovery key";
 
-  let ciphertext = cipher
-    .encrypt(&nonce, serialized.as_ref())
-    .map_err(|e| format!("Encryption failed: {}", e.to_string()))?;
+/// Random bytes in a recovery key (160 bits)
+const RECOVERY_KEY_BYTES: usize = 20;
+/// RFC 4648 base32, which has no 0/O or 1/I pairs to mix up when copying by hand
+const RECOVERY_KEY_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// Characters between dashes in a displayed recovery key
+const RECOVERY_KEY_GROUP_LEN: usize = 4;
+
+/// Makes a new random recovery key, as dash separated groups of base32
+/// characters, e.g. `ABCD-EFGH-...` (8 groups of 4).
+pub fn generate_recovery_key() -> Zeroizing<String> {
+  let mut bytes = Zeroizing::new([0u8; RECOVERY_KEY_BYTES]);
+  OsRng.fill_bytes(bytes.as_mut());
 
-  Ok(EncryptedVault {
+  let mut code = Zeroizing::new(String::new());
+  let mut written = 0;
+  // 5 bytes encode to exactly 8 base32 characters
+  for chunk in bytes.chunks(5) {
+    let mut bits = Zeroizing::new(0u64);
+    for byte in chunk {
+      *bits = (*bits << 8) | *byte as u64;
+    }
+    for i in (0..8).rev() {
+      if written > 0 && written % RECOVERY_KEY_GROUP_LEN == 0 {
+        code.push('-');
+      }
+      code.push(RECOVERY_KEY_ALPHABET[((*bits >> (i * 5)) & 0x1f) as usize] as char);
+      written += 1;
+    }
+  }
+  code
+}
+
+/// Canonical form of a typed recovery key: upper case, without dashes or spaces
+fn normalize_recovery_key(recovery_key: &str) -> Zeroizing<String> {
+  Zeroizing::new(
+    recovery_key
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric())
+      .map(|c| c.to_ascii_uppercase())
+      .collect(),
+  )
+}
+
+/// Derives a slot key from a password and wraps the data key with it
+fn seal_slot(
+  data_key: &[u8; 32],
+  label: &str,
+  password: &str,
+  kdf: &KdfParams,
+  key_file: Option<&KeyFile>,
+) -> Result<KeySlot, VaultError> {
+  let salt = gen_salt();
+  let slot_key = derive_key(password, &salt, kdf, key_file)?;
+  wrap_data_key(data_key, &slot_key, label, salt, kdf, key_file.is_some())
+}
+
+/// Wraps the data key with an already derived slot key
+fn wrap_data_key(
+  data_key: &[u8; 32],
+  slot_key: &[u8; 32],
+  label: &str,
+  salt: [u8; 16],
+  kdf: &KdfParams,
+  key_file: bool,
+) -> Result<KeySlot, VaultError> {
+  let cipher = Aes256Gcm::new_from_slice(slot_key).map_err(|e| VaultError::Crypto(e.to_string()))?;
+  let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+  let wrapped_key = cipher
+    .encrypt(&nonce, data_key.as_ref())
+    .map_err(|e| VaultError::Crypto(format!("Key wrapping failed: {}", e)))?;
+  Ok(KeySlot {
+    label: label.to_string(),
+    kdf: kdf.clone(),
     salt,
+    key_file,
+    recovery: false,
     nonce: nonce.into(),
-    ciphertext,
+    wrapped_key,
   })
 }
 
-/// Decrypts plaintext vault using a master password.
+/// Unwraps the data key from a slot with a password (and key file)
+/// 
+/// # Errors
+/// Returns [`VaultError::AuthenticationFailed`] if the password doesn't open the slot.
+fn open_slot(
+  slot: &KeySlot,
+  password: &str,
+  key_file: Option<&KeyFile>,
+) -> Result<Zeroizing<[u8; 32]>, VaultError> {
+  let slot_key = derive_key(password, &slot.salt, &slot.kdf, key_file)?;
+  let cipher = Aes256Gcm::new_from_slice(slot_key.as_ref())
+    .map_err(|e| VaultError::Crypto(e.to_string()))?;
+  let data_key = cipher
+    .decrypt(&slot.nonce.into(), slot.wrapped_key.as_ref())
+    .map(Zeroizing::new)
+    .map_err(|_| VaultError::AuthenticationFailed)?;
+  let mut key = Zeroizing::new([0u8; 32]);
+  if data_key.len() != key.len() {
+    return Err(VaultError::Malformed("key slot holds a key of the wrong size".into()));
+  }
+  key.copy_from_slice(&data_key);
+  Ok(key)
+}
+
+/// Unlocks an encrypted vault with a master password, and a key file for
+/// slots that need one.
+/// 
+/// Tries every key slot the given factors could open, each with its own
+/// KDF parameters. Vaults from before key slots (version 2 and older) are
+/// decrypted with the key in their header, and get a random data key in a
+/// single slot for the same password, written on the next save.
+/// 
+/// # Returns
+/// The plaintext vault and the [`SessionKey`] to re-encrypt it with.
+/// 
+/// # Errors 
+/// Returns [`VaultError::UnsupportedVersion`] if the header version is newer
+/// than this build understands, [`VaultError::KeyFileRequired`] if only
+/// slots with a key file are left to try, or the errors of [`derive_key`] and [`decrypt_vault`].
+pub fn unlock_vault(
+  encrypted: &EncryptedVault,
+  master_password: &str,
+  key_file: Option<&KeyFile>,
+) -> Result<(PasswordVault, SessionKey), VaultError> {
+  let header = &encrypted.header;
+  if header.version > VAULT_FORMAT_VERSION {
+    return Err(VaultError::UnsupportedVersion(header.version));
+  }
+  if header.version < KEY_SLOTS_FORMAT_VERSION {
+    return unlock_legacy_vault(encrypted, master_password, key_file);
+  }
+
+  let mut candidates: Vec<&KeySlot> = header
+    .key_slots
+    .iter()
+    .filter(|slot| !slot.recovery && (!slot.key_file || key_file.is_some()))
+    .collect();
+  if candidates.is_empty() {
+    return Err(VaultError::KeyFileRequired);
+  }
+  // A given key file most likely belongs to a slot that needs one
+  candidates.sort_by_key(|slot| !slot.key_file);
+  for slot in candidates {
+    let slot_key_file = key_file.filter(|_| slot.key_file);
+    let Ok(key) = open_slot(slot, master_password, slot_key_file) else {
+      continue;
+    };
+    let session_key = SessionKey {
+      key,
+      slots: header.key_slots.clone(),
+      unlocked_salt: slot.salt,
+      key_file: slot_key_file.cloned(),
+      cipher: header.cipher,
+    };
+    let vault = decrypt_vault(encrypted, &session_key)?;
+    return Ok((vault, session_key));
+  }
+  Err(VaultError::AuthenticationFailed)
+}
+
+/// Unlocks an encrypted vault with its recovery key, for when the master
+/// password (or key file) is lost. The session should set a new master
+/// password with [`SessionKey::reset_password`] before anything else.
 /// 
-/// Serializes the vault, derives a key, and decrypts the serialized data.
+/// Dashes, spaces and letter case in `recovery_key` are ignored.
+/// 
+/// # Errors 
+/// Returns [`VaultError::NoRecoveryKey`] if the vault has no recovery slot,
+/// [`VaultError::RecoveryKeyRejected`] if the code doesn't open it, or the
+/// errors of [`decrypt_vault`].
+pub fn unlock_with_recovery_key(
+  encrypted: &EncryptedVault,
+  recovery_key: &str,
+) -> Result<(PasswordVault, SessionKey), VaultError> {
+  let header = &encrypted.header;
+  if header.version > VAULT_FORMAT_VERSION {
+    return Err(VaultError::UnsupportedVersion(header.version));
+  }
+  let slot = header
+    .key_slots
+    .iter()
+    .find(|slot| slot.recovery)
+    .ok_or(VaultError::NoRecoveryKey)?;
+  let code = normalize_recovery_key(recovery_key);
+  let key = match open_slot(slot, &code, None) {
+    Ok(key) => key,
+    Err(VaultError::AuthenticationFailed) => return Err(VaultError::RecoveryKeyRejected),
+    Err(e) => return Err(e),
+  };
+  let session_key = SessionKey {
+    key,
+    slots: header.key_slots.clone(),
+    unlocked_salt: slot.salt,
+    key_file: None,
+    cipher: header.cipher,
+  };
+  let vault = decrypt_vault(encrypted, &session_key)?;
+  Ok((vault, session_key))
+}
+
+/// [`unlock_vault`] for vaults whose password-derived key encrypts the data directly
+fn unlock_legacy_vault(
+  encrypted: &EncryptedVault,
+  master_password: &str,
+  key_file: Option<&KeyFile>,
+) -> Result<(PasswordVault, SessionKey), VaultError> {
+  let header = &encrypted.header;
+  let (Some(kdf), Some(salt)) = (&header.kdf, header.salt) else {
+    return Err(VaultError::Malformed("header has no key slots or key derivation".into()));
+  };
+  let key_file = match (header.key_file, key_file) {
+    (true, None) => return Err(VaultError::KeyFileRequired),
+    (true, Some(key_file)) => Some(key_file),
+    (false, _) => None,
+  };
+  let slot_key = derive_key(master_password, &salt, kdf, key_file)?;
+  let vault = decrypt_with(encrypted, &slot_key)?;
+
+  let mut key = Zeroizing::new([0u8; 32]);
+  OsRng.fill_bytes(key.as_mut());
+  let slot = wrap_data_key(&key, &slot_key, OWNER_SLOT_LABEL, salt, kdf, key_file.is_some())?;
+  let session_key = SessionKey {
+    key,
+    unlocked_salt: slot.salt,
+    slots: vec![slot],
+    key_file: key_file.cloned(),
+    cipher: header.cipher,
+  };
+  Ok((vault, session_key))
+}
+
+/// First format version whose data key is wrapped in key slots
+const KEY_SLOTS_FORMAT_VERSION: u32 = 3;
+
+/// First format version whose header is authenticated as associated data
+const AAD_FORMAT_VERSION: u32 = 2;
+
+/// Domain separation for [`key_check`]
+const KEY_CHECK_CONTEXT: &[u8] = b"pixelvault key check";
+
+/// One-way hash of a derived key, stored in the header.
+///
+/// Lets decryption failures be blamed on the password or on the header.
+/// Guessing passwords against it costs as much as against the ciphertext,
+/// since the key still has to be derived with the KDF.
+fn key_check(key: &[u8; 32]) -> [u8; 32] {
+  Blake2b::<U32>::new()
+    .chain_update(KEY_CHECK_CONTEXT)
+    .chain_update(key)
+    .finalize()
+    .into()
+}
+
+/// Associated data authenticated with the ciphertext: the header and the
+/// unencrypted metadata, as stored (see [`SealedHeader`]).
+/// Vaults older than [`AAD_FORMAT_VERSION`] were encrypted without any.
+fn associated_data(encrypted: &EncryptedVault) -> Result<Vec<u8>, VaultError> {
+  if encrypted.header.version < AAD_FORMAT_VERSION {
+    return Ok(Vec::new());
+  }
+  let sealed = encrypted
+    .sealed_header()
+    .map_err(|e| VaultError::Malformed(e.to_string()))?;
+  Ok(sealed.associated_data())
+}
+
+/// Encrypts plaintext vault with a session key.
+/// 
+/// Serializes the vault and encrypts the serialized data.
+/// The returned vault always carries a current-version header.
 /// 
 /// # Security
-/// - Decrypts data with AES-256-GCM.
+/// - Encrypts data with the session's cipher, see [`SessionKey::cipher`].
 /// - Nonce is generated using OsRng each time.
+/// - Key slots are copied from the session key, no key derivation happens here.
+/// - Header and unencrypted metadata are bound to the ciphertext as associated data.
+/// 
+/// # Errors 
+/// Returns an error if encryption or serialization fails.
+pub fn encrypt_vault(
+  vault: &PasswordVault,
+  key: &SessionKey,
+) -> Result<EncryptedVault, VaultError> {
+  // Serialize from vault => bytes, wiped once encrypted
+  let serialized = Zeroizing::new(
+    serde_json::to_vec(&vault).map_err(|e| VaultError::Schema(e.to_string()))?,
+  );
+
+  let mut encrypted = EncryptedVault {
+    header: VaultHeader {
+      version: VAULT_FORMAT_VERSION,
+      kdf: None,
+      salt: None,
+      cipher: key.cipher,
+      key_check: Some(key_check(&key.key)),
+      key_file: false,
+      key_slots: key.slots.clone(),
+    },
+    metadata: vault.public_metadata(),
+    nonce: Vec::new(),
+    ciphertext: Vec::new(),
+    sealed_header: None,
+  };
+  // Serialize the header once, so the file stores exactly what was authenticated
+  let sealed = encrypted
+    .sealed_header()
+    .map_err(|e| VaultError::Schema(e.to_string()))?;
+  encrypted.sealed_header = Some(sealed);
+  // Nonce length is implied by the cipher, so it isn't part of the associated data
+  let aad = associated_data(&encrypted)?;
+  let payload = Payload { msg: serialized.as_ref(), aad: &aad };
+  let (nonce, ciphertext) = match key.cipher {
+    CipherKind::Aes256Gcm => seal_with::<Aes256Gcm>(&key.key, payload)?,
+    CipherKind::XChaCha20Poly1305 => seal_with::<XChaCha20Poly1305>(&key.key, payload)?,
+  };
+  encrypted.nonce = nonce;
+  encrypted.ciphertext = ciphertext;
+  Ok(encrypted)
+}
+
+/// Decrypts plaintext vault with a session key.
+/// 
+/// # Security
+/// - Decrypts data with the cipher named in the header.
+/// - Authenticates the header and unencrypted metadata as associated data.
 /// 
 /// # Errors 
-/// Returns an error if key derivation, encryption, or serialization fails.
+/// - [`VaultError::AuthenticationFailed`] if the key does not match the vault
+/// - [`VaultError::DataTampered`] if the key matches the header's key check,
+///   but the ciphertext or header was corrupted or modified
+/// - [`VaultError::Schema`] if the plaintext isn't a valid vault
 pub fn decrypt_vault(
   encrypted: &EncryptedVault,
-  master_password: &str,
-) -> Result<PasswordVault, String> {
-  let key = derive_key(master_password, &encrypted.salt)?;
-  let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+  key: &SessionKey,
+) -> Result<PasswordVault, VaultError> {
+  decrypt_with(encrypted, &key.key)
+}
 
-  let plaintext = cipher
-    .decrypt(&encrypted.nonce.into(), encrypted.ciphertext.as_ref())
-    .map_err(|e| format!("Invalid password: {}", e))?;
+/// [`decrypt_vault`] with the raw key the entries were encrypted with
+fn decrypt_with(encrypted: &EncryptedVault, key: &[u8; 32]) -> Result<PasswordVault, VaultError> {
+  let header = &encrypted.header;
+  let aad = associated_data(encrypted)?;
+  let payload = Payload { msg: encrypted.ciphertext.as_ref(), aad: &aad };
+  let plaintext = match header.cipher {
+    CipherKind::Aes256Gcm => open_with::<Aes256Gcm>(key, &encrypted.nonce, payload)?,
+    CipherKind::XChaCha20Poly1305 => {
+      open_with::<XChaCha20Poly1305>(key, &encrypted.nonce, payload)?
+    }
+  };
+  let plaintext = match plaintext {
+    Some(plaintext) => plaintext,
+    None if header.key_check == Some(key_check(key)) => {
+      return Err(VaultError::DataTampered);
+    }
+    None => return Err(VaultError::AuthenticationFailed),
+  };
 
   // Deserialize from bytes => vault
   let vault = serde_json::from_slice(&plaintext)
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| VaultError::Schema(e.to_string()))?;
   
   Ok(vault)
 }
 
+/// Encrypts with AEAD `C` under a fresh random nonce
+/// 
+/// # Returns
+/// The nonce and the ciphertext.
+fn seal_with<C: Aead + AeadCore + KeyInit>(
+  key: &[u8; 32],
+  payload: Payload<'_, '_>,
+) -> Result<(Vec<u8>, Vec<u8>), VaultError> {
+  let cipher = C::new_from_slice(key).map_err(|e| VaultError::Crypto(e.to_string()))?;
+  // Use OsRng for cryptographic randomness
+  let nonce = C::generate_nonce(&mut OsRng);
+  let ciphertext = cipher
+    .encrypt(&nonce, payload)
+    .map_err(|e| VaultError::Crypto(format!("Encryption failed: {}", e)))?;
+  Ok((nonce.to_vec(), ciphertext))
+}
+
+/// Decrypts with AEAD `C`, `None` if the ciphertext doesn't authenticate
+/// 
+/// # Errors
+/// Returns [`VaultError::Malformed`] if the nonce has the wrong length for `C`.
+fn open_with<C: Aead + KeyInit>(
+  key: &[u8; 32],
+  nonce: &[u8],
+  payload: Payload<'_, '_>,
+) -> Result<Option<Zeroizing<Vec<u8>>>, VaultError> {
+  let cipher = C::new_from_slice(key).map_err(|e| VaultError::Crypto(e.to_string()))?;
+  let nonce = Nonce::<C>::from_exact_iter(nonce.iter().copied())
+    .ok_or_else(|| VaultError::Malformed("nonce has the wrong length for the cipher".into()))?;
+  Ok(cipher.decrypt(&nonce, payload).ok().map(Zeroizing::new))
+}
+
 /// Generates a random salt for key derivation.
 /// 
 /// # Security
@@ -118,4 +822,220 @@ pub fn gen_salt() -> [u8; 16] {
   let mut salt = [0u8; 16];
   OsRng.fill_bytes(&mut salt);
   salt
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    models::{SealedHeader, VaultMetadata},
+    test_util::{TEST_KDF, sample_vault, temp_path},
+    vault,
+  };
+
+  /// Stored container bytes of the sample vault
+  fn sample_container(key: &SessionKey) -> Vec<u8> {
+    vault::encode(&encrypt_vault(&sample_vault(), key).unwrap()).unwrap()
+  }
+
+  /// Replaces `from` with `to` (same length) inside the stored bytes
+  fn patch(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let at = data.windows(from.len()).position(|w| w == from).unwrap();
+    let mut patched = data.to_vec();
+    patched[at..at + to.len()].copy_from_slice(to);
+    patched
+  }
+
+  #[test]
+  fn encrypt_then_unlock() {
+    let key = new_session_key("pw", &TEST_KDF, None).unwrap();
+    let stored = vault::decode(&sample_container(&key)).unwrap();
+    let (vault, _) = unlock_vault(&stored, "pw", None).unwrap();
+    assert_eq!(vault.entries[0].password, "hunter2");
+    assert!(matches!(unlock_vault(&stored, "wrong", None), Err(VaultError::AuthenticationFailed)));
+  }
+
+  #[test]
+  fn tampered_metadata_is_detected() {
+    let key = new_session_key("pw", &TEST_KDF, None).unwrap();
+    let data = patch(&sample_container(&key), b"\"Sample\"", b"\"Simple\"");
+    let stored = vault::decode(&data).unwrap();
+    assert_eq!(stored.metadata.as_ref().unwrap().name, "Simple");
+    assert!(matches!(decrypt_vault(&stored, &key), Err(VaultError::DataTampered)));
+    assert!(matches!(unlock_vault(&stored, "pw", None), Err(VaultError::DataTampered)));
+  }
+
+  #[test]
+  fn tampered_kdf_is_detected() {
+    let key = new_session_key("pw", &TEST_KDF, None).unwrap();
+    let data = patch(&sample_container(&key), b"\"iterations\":1", b"\"iterations\":2");
+    let stored = vault::decode(&data).unwrap();
+    assert_eq!(stored.header.key_slots[0].kdf.iterations, 2);
+    // An open session still matches the key check, so the header is blamed
+    assert!(matches!(decrypt_vault(&stored, &key), Err(VaultError::DataTampered)));
+    // Unlocking derives the slot key with the changed costs, which then
+    // can't unwrap the data key, the same as a wrong password
+    assert!(matches!(unlock_vault(&stored, "pw", None), Err(VaultError::AuthenticationFailed)));
+  }
+
+  #[test]
+  fn stored_header_is_written_back_unchanged() {
+    let key = new_session_key("pw", &TEST_KDF, None).unwrap();
+    let data = sample_container(&key);
+    assert_eq!(vault::encode(&vault::decode(&data).unwrap()).unwrap(), data);
+  }
+
+  #[test]
+  fn header_fields_unknown_to_this_build_still_authenticate() {
+    // A header as a later build might write it, sealed with its own bytes
+    let key = new_session_key("pw", &TEST_KDF, None).unwrap();
+    let mut encrypted = encrypt_vault(&sample_vault(), &key).unwrap();
+    let mut header = serde_json::to_value(&encrypted.header).unwrap();
+    header["added_later"] = serde_json::json!(true);
+    encrypted.sealed_header = Some(SealedHeader {
+      header: serde_json::value::to_raw_value(&header).unwrap(),
+      metadata: encrypted.sealed_header.unwrap().metadata,
+    });
+    let aad = associated_data(&encrypted).unwrap();
+    let payload = Payload { msg: br#"{"entries":[]}"#, aad: &aad };
+    let (nonce, ciphertext) = seal_with::<Aes256Gcm>(&key.key, payload).unwrap();
+    encrypted.nonce = nonce;
+    encrypted.ciphertext = ciphertext;
+
+    let stored = vault::decode(&vault::encode(&encrypted).unwrap()).unwrap();
+    assert!(decrypt_vault(&stored, &key).is_ok());
+  }
+
+  #[test]
+  fn version_2_vault_from_before_key_files_unlocks() {
+    // Written the way version 2 vaults were, before `key_file` was in the header
+    let salt = [3u8; 16];
+    let key = derive_key("pw", &salt, &TEST_KDF, None).unwrap();
+    let header = format!(
+      r#"{{"version":2,"kdf":{},"salt":{},"cipher":"Aes256Gcm","key_check":{}}}"#,
+      serde_json::to_string(&TEST_KDF).unwrap(),
+      serde_json::to_string(&salt).unwrap(),
+      serde_json::to_string(&key_check(&key)).unwrap(),
+    );
+    let metadata = serde_json::to_string(&VaultMetadata::new("Old")).unwrap();
+    let aad = format!("[{},{}]", header, metadata);
+    let plaintext = br#"{"entries":[{"service":"a","username":"b","password":"c"}]}"#;
+    let payload = Payload { msg: plaintext, aad: aad.as_bytes() };
+    let (nonce, ciphertext) = seal_with::<Aes256Gcm>(&key, payload).unwrap();
+
+    let container = format!(
+      r#"{{"header":{},"metadata":{},"nonce":{}}}"#,
+      header,
+      metadata,
+      serde_json::to_string(&nonce).unwrap(),
+    );
+    let mut data = b"PXVAULT\0".to_vec();
+    data.extend_from_slice(&(container.len() as u32).to_le_bytes());
+    data.extend_from_slice(container.as_bytes());
+    data.extend_from_slice(&ciphertext);
+
+    let stored = vault::decode(&data).unwrap();
+    assert!(!stored.header.key_file);
+    assert_eq!(serde_json::to_string(&stored.header).unwrap(), header);
+    let (vault, _) = unlock_vault(&stored, "pw", None).unwrap();
+    assert_eq!(vault.entries[0].password, "c");
+  }
+
+  #[test]
+  fn each_key_slot_opens_the_vault() {
+    let mut key = new_session_key("owner pw", &TEST_KDF, None).unwrap();
+    key.add_slot("alice", "alice pw", &TEST_KDF, None).unwrap();
+    let stored = encrypt_vault(&sample_vault(), &key).unwrap();
+
+    let (_, owner) = unlock_vault(&stored, "owner pw", None).unwrap();
+    assert!(owner.is_unlocked_slot(&owner.slots()[0]));
+    let (_, alice) = unlock_vault(&stored, "alice pw", None).unwrap();
+    assert!(alice.is_unlocked_slot(&alice.slots()[1]));
+    assert_eq!(*owner.key, *alice.key);
+  }
+
+  #[test]
+  fn removed_slot_no_longer_opens_the_vault() {
+    let mut key = new_session_key("owner pw", &TEST_KDF, None).unwrap();
+    key.add_slot("alice", "alice pw", &TEST_KDF, None).unwrap();
+    let stored = encrypt_vault(&sample_vault(), &key).unwrap();
+    let (_, mut alice) = unlock_vault(&stored, "alice pw", None).unwrap();
+    // The slot the session was unlocked with can't be removed
+    assert!(alice.remove_slot(1).is_err());
+    alice.remove_slot(0).unwrap();
+
+    let stored = encrypt_vault(&sample_vault(), &alice).unwrap();
+    let owner = unlock_vault(&stored, "owner pw", None);
+    assert!(matches!(owner, Err(VaultError::AuthenticationFailed)));
+    assert!(unlock_vault(&stored, "alice pw", None).is_ok());
+  }
+
+  #[test]
+  fn changing_a_password_keeps_the_data_key() {
+    let mut key = new_session_key("old pw", &TEST_KDF, None).unwrap();
+    let before = encrypt_vault(&sample_vault(), &key).unwrap();
+    key.change_password("new pw", &TEST_KDF).unwrap();
+    let after = encrypt_vault(&sample_vault(), &key).unwrap();
+
+    assert!(unlock_vault(&after, "old pw", None).is_err());
+    assert!(unlock_vault(&after, "new pw", None).is_ok());
+    // Copies saved before the change still decrypt in this session
+    assert!(decrypt_vault(&before, &key).is_ok());
+  }
+
+  #[test]
+  fn key_file_slot_needs_the_key_file() {
+    let path = temp_path("key-file", "vault.key");
+    let key_file = generate_key_file(&path).unwrap();
+    assert!(generate_key_file(&path).is_err());
+
+    let key = new_session_key("pw", &TEST_KDF, Some(key_file)).unwrap();
+    let stored = encrypt_vault(&sample_vault(), &key).unwrap();
+    assert!(matches!(unlock_vault(&stored, "pw", None), Err(VaultError::KeyFileRequired)));
+    let key_file = KeyFile::read(&path).unwrap();
+    assert!(unlock_vault(&stored, "pw", Some(&key_file)).is_ok());
+  }
+
+  #[test]
+  fn recovery_key_unlocks_and_resets_the_password() {
+    let mut key = new_session_key("owner pw", &TEST_KDF, None).unwrap();
+    let without = encrypt_vault(&sample_vault(), &key).unwrap();
+    assert!(matches!(unlock_with_recovery_key(&without, "x"), Err(VaultError::NoRecoveryKey)));
+
+    let code = generate_recovery_key();
+    key.set_recovery_key(&code).unwrap();
+    let stored = encrypt_vault(&sample_vault(), &key).unwrap();
+    let wrong = unlock_with_recovery_key(&stored, "AAAA-BBBB");
+    assert!(matches!(wrong, Err(VaultError::RecoveryKeyRejected)));
+    // The recovery key isn't a password
+    assert!(unlock_vault(&stored, &code, None).is_err());
+
+    let typed = code.to_lowercase().replace('-', " ");
+    let (_, mut recovered) = unlock_with_recovery_key(&stored, &typed).unwrap();
+    assert!(recovered.unlocked_with_recovery_key());
+    recovered.reset_password("new owner pw").unwrap();
+    assert!(!recovered.unlocked_with_recovery_key());
+
+    let reset = encrypt_vault(&sample_vault(), &recovered).unwrap();
+    assert!(unlock_vault(&reset, "owner pw", None).is_err());
+    assert!(unlock_vault(&reset, "new owner pw", None).is_ok());
+    assert!(unlock_with_recovery_key(&reset, &code).is_ok());
+  }
+
+  #[test]
+  fn xchacha_vault_round_trips() {
+    let mut key = new_session_key("pw", &TEST_KDF, None).unwrap();
+    key.set_cipher(CipherKind::XChaCha20Poly1305);
+    let data = sample_container(&key);
+    let stored = vault::decode(&data).unwrap();
+    assert_eq!(stored.nonce.len(), 24);
+
+    let (vault, unlocked) = unlock_vault(&stored, "pw", None).unwrap();
+    assert_eq!(vault.entries[0].password, "hunter2");
+    assert_eq!(unlocked.cipher(), CipherKind::XChaCha20Poly1305);
+
+    // Claiming the other cipher in the header fails to decrypt
+    let swapped = patch(&data, b"XChaCha20Poly1305\"", b"Aes256Gcm\"        ");
+    assert!(decrypt_vault(&vault::decode(&swapped).unwrap(), &unlocked).is_err());
+  }
+}
+
@@ -0,0 +1,237 @@
+//! The `lock` module handles exclusive vault lock files.
+//!
+//! Responsibilities:
+//! - Take an advisory lock on a vault, so two instances don't clobber it.
+//! - Record who holds the lock (PID and host).
+//! - Detect and replace stale locks left behind by crashed instances.
+//!
+//! The lock is a `<vault>.lock` file created with `create_new`, and is
+//! removed again when the [`VaultLock`] guard that created it is dropped.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+  fs::{self, OpenOptions},
+  io::{ErrorKind, Write},
+};
+
+/// Who holds a vault lock, stored as JSON in the lock file
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct LockOwner {
+  pub pid: u32,
+  pub host: String,
+  pub acquired: DateTime<Utc>,
+}
+
+impl LockOwner {
+  /// Owner describing the current process
+  fn current() -> Self {
+    Self {
+      pid: std::process::id(),
+      host: hostname(),
+      acquired: Utc::now(),
+    }
+  }
+
+  /// Owner of a lock file that can't be read, e.g. one another instance
+  /// has just created but not written yet
+  fn unknown(acquired: DateTime<Utc>) -> Self {
+    Self {
+      pid: 0,
+      host: String::new(),
+      acquired,
+    }
+  }
+
+  /// Whether the lock file named its owner
+  pub fn is_known(&self) -> bool {
+    self.pid != 0
+  }
+
+  /// Whether the owner is known to be gone.
+  ///
+  /// Only locks from this host can be checked, and only where process
+  /// liveness can be queried (Linux `/proc`). Anything else is assumed alive.
+  fn is_stale(&self) -> bool {
+    if !self.is_known() || self.host != hostname() {
+      return false;
+    }
+    // Our own PID means a guard from this process that wasn't dropped
+    if self.pid == std::process::id() {
+      return true;
+    }
+    cfg!(target_os = "linux") && !std::path::Path::new(&format!("/proc/{}", self.pid)).exists()
+  }
+}
+
+/// Why a lock couldn't be taken
+pub enum LockError {
+  /// Another live instance holds the lock
+  Held(LockOwner),
+  /// The lock file couldn't be read or written
+  Io(String),
+}
+
+impl std::fmt::Display for LockError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LockError::Held(owner) if !owner.is_known() => {
+        write!(f, "Vault is being opened elsewhere (unreadable lock file)")
+      }
+      LockError::Held(owner) => write!(
+        f,
+        "Vault is open elsewhere (PID {} on {})",
+        owner.pid, owner.host
+      ),
+      LockError::Io(e) => write!(f, "Vault lock failed: {}", e),
+    }
+  }
+}
+
+/// Guard for a held vault lock, the lock file is removed on drop
+pub struct VaultLock {
+  path: String,
+  /// Owner written into the lock file, to tell if another instance took it over
+  owner: LockOwner,
+}
+
+impl Drop for VaultLock {
+  fn drop(&mut self) {
+    if read_owner(&self.path).is_some_and(|owner| owner == self.owner) {
+      fs::remove_file(&self.path).ok();
+    }
+  }
+}
+
+/// Path of the lock file for a vault, `name.pvault.lock`
+pub fn lock_path(vault_path: &str) -> String {
+  format!("{}.lock", vault_path)
+}
+
+/// Takes the lock for a vault, replacing it if it is stale.
+///
+/// # Errors
+/// Returns [`LockError::Held`] if another live instance holds the lock,
+/// or [`LockError::Io`] if the lock file can't be created.
+pub fn acquire(vault_path: &str) -> Result<VaultLock, LockError> {
+  let path = lock_path(vault_path);
+  match try_create(&path) {
+    Err(LockError::Held(owner)) if owner.is_stale() => {
+      fs::remove_file(&path).map_err(|e| LockError::Io(e.to_string()))?;
+      try_create(&path)
+    }
+    result => result,
+  }
+}
+
+/// Takes the lock for a vault even if another instance holds it.
+///
+/// # Errors
+/// Returns an error if the lock file can't be replaced.
+pub fn force_acquire(vault_path: &str) -> Result<VaultLock, LockError> {
+  let path = lock_path(vault_path);
+  match fs::remove_file(&path) {
+    Err(e) if e.kind() != ErrorKind::NotFound => return Err(LockError::Io(e.to_string())),
+    _ => {}
+  }
+  try_create(&path)
+}
+
+/// Atomically creates the lock file and writes the current owner into it
+fn try_create(path: &str) -> Result<VaultLock, LockError> {
+  let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+    Ok(file) => file,
+    Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+      let owner = read_owner(path).unwrap_or_else(|| {
+        let modified = fs::metadata(path).and_then(|m| m.modified());
+        LockOwner::unknown(modified.map(DateTime::from).unwrap_or_else(|_| Utc::now()))
+      });
+      return Err(LockError::Held(owner));
+    }
+    Err(e) => return Err(LockError::Io(e.to_string())),
+  };
+  let owner = LockOwner::current();
+  let data = serde_json::to_vec(&owner).map_err(|e| LockError::Io(e.to_string()))?;
+  if let Err(e) = file.write_all(&data).and_then(|_| file.sync_all()) {
+    fs::remove_file(path).ok();
+    return Err(LockError::Io(e.to_string()));
+  }
+  Ok(VaultLock { path: path.to_string(), owner })
+}
+
+/// Reads the owner of an existing lock file, `None` if it can't be read.
+///
+/// An unreadable lock is never treated as stale: it may belong to an
+/// instance that has created the file but not written its owner yet.
+fn read_owner(path: &str) -> Option<LockOwner> {
+  fs::read(path)
+    .ok()
+    .and_then(|data| serde_json::from_slice(&data).ok())
+}
+
+/// Best-effort name of this machine
+fn hostname() -> String {
+  fs::read_to_string("/etc/hostname")
+    .ok()
+    .or_else(|| std::env::var("COMPUTERNAME").ok())
+    .or_else(|| std::env::var("HOSTNAME").ok())
+    .map(|h| h.trim().to_string())
+    .filter(|h| !h.is_empty())
+    .unwrap_or_else(|| "unknown host".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::temp_path;
+
+  fn write_owner(vault_path: &str, pid: u32) {
+    let owner = LockOwner { pid, host: hostname(), acquired: Utc::now() };
+    fs::write(lock_path(vault_path), serde_json::to_vec(&owner).unwrap()).unwrap();
+  }
+
+  #[test]
+  fn lock_is_removed_on_drop() {
+    let path = temp_path("lock-drop", "vault.pvault");
+    let guard = acquire(&path).ok().unwrap();
+    assert!(fs::exists(lock_path(&path)).unwrap());
+    drop(guard);
+    assert!(!fs::exists(lock_path(&path)).unwrap());
+  }
+
+  #[test]
+  fn stale_lock_is_replaced() {
+    let path = temp_path("lock-stale", "vault.pvault");
+    write_owner(&path, u32::MAX);
+    assert!(acquire(&path).is_ok());
+  }
+
+  #[test]
+  #[cfg(target_os = "linux")]
+  fn live_lock_is_held() {
+    let path = temp_path("lock-live", "vault.pvault");
+    write_owner(&path, 1);
+    assert!(matches!(acquire(&path), Err(LockError::Held(owner)) if owner.pid == 1));
+  }
+
+  #[test]
+  fn unreadable_lock_is_held() {
+    // What another instance's lock looks like between create and write
+    let path = temp_path("lock-empty", "vault.pvault");
+    fs::write(lock_path(&path), b"").unwrap();
+    assert!(matches!(acquire(&path), Err(LockError::Held(owner)) if !owner.is_known()));
+    assert!(fs::exists(lock_path(&path)).unwrap());
+  }
+
+  #[test]
+  fn taken_over_lock_survives_old_guard() {
+    let path = temp_path("lock-takeover", "vault.pvault");
+    let old = acquire(&path).ok().unwrap();
+    // A takeover from another instance, which writes a different owner
+    write_owner(&path, 1);
+    drop(old);
+    assert!(fs::exists(lock_path(&path)).unwrap());
+    let forced = force_acquire(&path).ok().unwrap();
+    drop(forced);
+    assert!(!fs::exists(lock_path(&path)).unwrap());
+  }
+}
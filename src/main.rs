@@ -7,15 +7,23 @@
 //! # Overview 
 //! - egui-based desktop UI
 //! - Argon2 key derivation
-//! - AES-GCM authenticated encryption
+//! - AES-GCM or XChaCha20-Poly1305 authenticated encryption
 //! 
 //! This crate is intended to be run as an executable, not used as a library. 
 mod app;  
+mod clipboard;
+mod domain;
+mod error;
 mod krypt;
+mod lock;
+mod merge;
 mod models;
 mod vault;
 mod search;
+mod settings;
 mod pw_gen;
+#[cfg(test)]
+mod test_util;
 
 use eframe::egui;
 use app::PixelVaultApp;
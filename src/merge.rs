@@ -0,0 +1,155 @@
+//! The `merge` module handles merging vaults that were changed in two places.
+//!
+//! # Responsibilities
+//! - Three-way merge of password entries against a common base.
+//! - Reporting entries changed on both sides as conflicts.
+//!
+//! Entries are matched by their ID, so renaming an entry on one side is a
+//! change to it rather than a delete and an add.
+use crate::models::{PasswordEntry, PasswordVault};
+use uuid::Uuid;
+
+/// An entry changed differently in both vaults.
+/// `None` means that side deleted the entry.
+pub struct EntryConflict {
+  pub mine: Option<PasswordEntry>,
+  pub theirs: Option<PasswordEntry>,
+  /// Side picked by the user, if any
+  pub resolution: Option<Resolution>,
+}
+
+/// Which side of a conflict to keep
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+  Mine,
+  Theirs,
+}
+
+/// Result of [`three_way`]
+pub struct MergeOutcome {
+  /// Entries that merged cleanly, in order
+  pub entries: Vec<PasswordEntry>,
+  /// Entries that need the user to pick a side
+  pub conflicts: Vec<EntryConflict>,
+}
+
+impl EntryConflict {
+  /// The entry as one side has it, for naming it to the user
+  pub fn entry(&self) -> Option<&PasswordEntry> {
+    self.mine.as_ref().or(self.theirs.as_ref())
+  }
+}
+
+impl MergeOutcome {
+  /// Entries with every conflict resolved, or `None` while any is unresolved.
+  pub fn resolved_entries(&self) -> Option<Vec<PasswordEntry>> {
+    let mut entries = self.entries.clone();
+    for conflict in &self.conflicts {
+      let kept = match conflict.resolution? {
+        Resolution::Mine => &conflict.mine,
+        Resolution::Theirs => &conflict.theirs,
+      };
+      entries.extend(kept.clone());
+    }
+    Some(entries)
+  }
+}
+
+/// Three-way merge of `mine` and `theirs`, which both started from `base`.
+///
+/// Per entry: a change on only one side wins, identical changes agree,
+/// and different changes on both sides become an [`EntryConflict`].
+///
+/// # Returns
+/// Cleanly merged entries (mine first, then new ones of theirs) and conflicts.
+pub fn three_way(base: &PasswordVault, mine: &PasswordVault, theirs: &PasswordVault) -> MergeOutcome {
+  let find = |side: &PasswordVault, id: Uuid| side.entry(id).cloned();
+
+  // Every ID once, keeping the order of mine
+  let mut ids: Vec<Uuid> = Vec::new();
+  for entry in mine.entries.iter().chain(&theirs.entries).chain(&base.entries) {
+    if !ids.contains(&entry.id) {
+      ids.push(entry.id);
+    }
+  }
+
+  let mut outcome = MergeOutcome { entries: Vec::new(), conflicts: Vec::new() };
+  for id in ids {
+    let b = find(base, id);
+    let m = find(mine, id);
+    let t = find(theirs, id);
+
+    let merged = if m == t || t == b {
+      m
+    } else if m == b {
+      t
+    } else {
+      outcome.conflicts.push(EntryConflict { mine: m, theirs: t, resolution: None });
+      continue;
+    };
+    outcome.entries.extend(merged);
+  }
+  outcome
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn vault(entries: &[&PasswordEntry]) -> PasswordVault {
+    let mut vault = PasswordVault::new("Merge");
+    vault.entries = entries.iter().map(|&entry| entry.clone()).collect();
+    vault
+  }
+
+  fn with_password(entry: &PasswordEntry, password: &str) -> PasswordEntry {
+    let mut changed = entry.clone();
+    changed.set_password(password, 10);
+    changed
+  }
+
+  #[test]
+  fn changes_on_one_side_merge() {
+    let a = PasswordEntry::new("a", "me", "x");
+    let b = PasswordEntry::new("b", "me", "x");
+    let c = PasswordEntry::new("c", "me", "x");
+    let added_by_them = PasswordEntry::new("d", "me", "x");
+    let base = vault(&[&a, &b, &c]);
+    let mine = vault(&[&with_password(&a, "mine"), &b]);
+    let theirs = vault(&[&a, &b, &c, &added_by_them]);
+
+    let outcome = three_way(&base, &mine, &theirs);
+    assert!(outcome.conflicts.is_empty());
+    let merged: Vec<_> =
+      outcome.entries.iter().map(|e| (e.service.as_str(), e.password.as_str())).collect();
+    assert_eq!(merged, [("a", "mine"), ("b", "x"), ("d", "x")]);
+  }
+
+  #[test]
+  fn rename_keeps_the_entry() {
+    let a = PasswordEntry::new("a", "me", "x");
+    let mut renamed = a.clone();
+    renamed.service = "A".into();
+    let base = vault(&[&a]);
+
+    let outcome = three_way(&base, &vault(&[&renamed]), &base);
+    assert_eq!(outcome.entries.len(), 1);
+    assert_eq!(outcome.entries[0].service, "A");
+  }
+
+  #[test]
+  fn changes_on_both_sides_conflict() {
+    let a = PasswordEntry::new("a", "me", "x");
+    let base = vault(&[&a]);
+    let mine = vault(&[&with_password(&a, "mine")]);
+    let theirs = vault(&[&with_password(&a, "theirs")]);
+
+    let mut outcome = three_way(&base, &mine, &theirs);
+    assert!(outcome.entries.is_empty());
+    assert_eq!(outcome.conflicts.len(), 1);
+    assert!(outcome.resolved_entries().is_none());
+
+    outcome.conflicts[0].resolution = Some(Resolution::Theirs);
+    assert_eq!(outcome.resolved_entries().unwrap()[0].password, "theirs");
+  }
+}
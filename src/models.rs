@@ -1,37 +1,435 @@
 //! The `models` module handles data models used for the vault.
-//! 
+//!
 //! # Responsibilities
 //! - Defines data structures for storing and managing passwords.
 //! - Implements serialization and deserialization for data persistence.
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-/// Plaintext vault stored only in local memory
-#[derive(Serialize, Deserialize)]
+/// Vault file format version written by `vault::save`.
+///
+/// Version `0` is the original header-less format, which is only ever read.
+/// From version `2` the header and metadata are authenticated with the ciphertext.
+/// From version `3` the data key is wrapped in [`KeySlot`]s.
+/// From version `4` the cipher can be XChaCha20-Poly1305, with a longer nonce.
+pub const VAULT_FORMAT_VERSION: u32 = 4;
+
+/// Plaintext vault stored only in local memory, wiped on drop
+#[derive(Serialize, Deserialize, Clone, Zeroize, ZeroizeOnDrop)]
 pub struct PasswordVault {
   /// Decrypted entries for passwords
   pub entries: Vec<PasswordEntry>,
+  /// Name and other details of the vault, `None` for vaults made before metadata
+  #[serde(default)]
+  #[zeroize(skip)]
+  pub metadata: Option<VaultMetadata>,
+  /// Keep the metadata only inside the ciphertext, so the vault list
+  /// shows just the file name
+  #[serde(default)]
+  pub encrypt_metadata: bool,
+}
+
+impl PasswordVault {
+  /// Empty vault with fresh metadata
+  pub fn new(name: &str) -> Self {
+    Self {
+      entries: Vec::new(),
+      metadata: Some(VaultMetadata::new(name)),
+      encrypt_metadata: false,
+    }
+  }
+
+  /// Updates the modified date and entry count, called before each save
+  pub fn touch(&mut self) {
+    let entry_count = self.entries.len();
+    if let Some(metadata) = self.metadata.as_mut() {
+      metadata.modified = Utc::now();
+      metadata.entry_count = entry_count;
+    }
+  }
+
+  /// Gives entries from before entry IDs (nil ID) their identity.
+  ///
+  /// Entries found in `known` by service and username, in order of
+  /// appearance, take its ID and timestamps, so two copies of the same old
+  /// vault still match up when merged. The rest get a new ID.
+  pub fn assign_entry_ids(&mut self, known: Option<&PasswordVault>) {
+    let mut claimed: Vec<Uuid> = Vec::new();
+    for entry in self.entries.iter_mut().filter(|entry| entry.id.is_nil()) {
+      let twin = known.and_then(|known| {
+        known.entries.iter().find(|k| {
+          k.service == entry.service && k.username == entry.username && !claimed.contains(&k.id)
+        })
+      });
+      match twin {
+        Some(twin) => {
+          entry.id = twin.id;
+          entry.created_at = twin.created_at;
+          entry.modified_at = twin.modified_at;
+        }
+        None => {
+          let now = Utc::now();
+          entry.id = Uuid::new_v4();
+          entry.created_at = now;
+          entry.modified_at = now;
+        }
+      }
+      claimed.push(entry.id);
+    }
+  }
+
+  /// Entry with the given ID
+  pub fn entry(&self, id: Uuid) -> Option<&PasswordEntry> {
+    self.entries.iter().find(|entry| entry.id == id)
+  }
+
+  /// Mutable entry with the given ID
+  pub fn entry_mut(&mut self, id: Uuid) -> Option<&mut PasswordEntry> {
+    self.entries.iter_mut().find(|entry| entry.id == id)
+  }
+
+  /// Metadata to store unencrypted next to the ciphertext, if allowed
+  pub fn public_metadata(&self) -> Option<VaultMetadata> {
+    if self.encrypt_metadata {
+      None
+    } else {
+      self.metadata.clone()
+    }
+  }
+}
+
+/// Descriptive details of a vault, independent of its file name
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VaultMetadata {
+  /// Name shown to the user, free to change without renaming the file
+  pub name: String,
+  #[serde(default)]
+  pub description: String,
+  pub created: DateTime<Utc>,
+  pub modified: DateTime<Utc>,
+  /// Number of entries as of the last save
+  pub entry_count: usize,
+}
+
+impl VaultMetadata {
+  /// Metadata for a vault made just now
+  pub fn new(name: &str) -> Self {
+    let now = Utc::now();
+    Self {
+      name: name.to_string(),
+      description: String::new(),
+      created: now,
+      modified: now,
+      entry_count: 0,
+    }
+  }
 }
 
 /// Encrypted on-disk representation of the vault.
-/// 
+///
 /// Contains all the information (except master password) needed
 /// to decrypt the vault.
 #[derive(Serialize, Deserialize)]
 pub struct EncryptedVault {
-  /// Random Argon2 salt
-  /// Must be unique per vault.
-  pub salt: [u8; 16],
-  /// AES-GCM nonce used for vault encryption
+  /// Unencrypted description of how the vault was encrypted
+  pub header: VaultHeader,
+  /// Unencrypted copy of the vault metadata, unless the user keeps it encrypted
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub metadata: Option<VaultMetadata>,
+  /// Nonce used for vault encryption, its length depends on the cipher
   /// Must never be reused with the same key.
-  pub nonce: [u8; 12],
+  pub nonce: Vec<u8>,
   /// Entire encrypted vault (serialized)
   pub ciphertext: Vec<u8>,
+  /// `header` and `metadata` exactly as stored, set by `krypt::encrypt_vault`
+  /// and when read from a binary container. `None` for the JSON layouts.
+  #[serde(skip)]
+  pub sealed_header: Option<SealedHeader>,
 }
 
-/// Plaintext password entry containing a service, username, and password.
+impl EncryptedVault {
+  /// The header and metadata as stored, serialized from `header` and
+  /// `metadata` if the vault has no stored form yet
+  ///
+  /// # Errors
+  /// Returns an error if the header can't be serialized.
+  pub fn sealed_header(&self) -> serde_json::Result<SealedHeader> {
+    match &self.sealed_header {
+      Some(sealed) => Ok(sealed.clone()),
+      None => SealedHeader::new(&self.header, self.metadata.as_ref()),
+    }
+  }
+}
+
+/// Header and unencrypted metadata of a vault as serialized JSON, kept
+/// byte for byte as they were when the vault was encrypted.
+///
+/// They're authenticated as associated data, so they're written back
+/// as is rather than serialized again. Fields added to [`VaultHeader`] or
+/// [`VaultMetadata`] later then can't change what existing vaults authenticate.
+#[derive(Clone)]
+pub struct SealedHeader {
+  pub header: Box<RawValue>,
+  pub metadata: Option<Box<RawValue>>,
+}
+
+impl SealedHeader {
+  /// Serializes a header and metadata for sealing
+  ///
+  /// # Errors
+  /// Returns an error if either can't be serialized.
+  pub fn new(header: &VaultHeader, metadata: Option<&VaultMetadata>) -> serde_json::Result<Self> {
+    Ok(Self {
+      header: serde_json::value::to_raw_value(header)?,
+      metadata: metadata.map(serde_json::value::to_raw_value).transpose()?,
+    })
+  }
+
+  /// Associated data of the ciphertext: the JSON array `[header, metadata]`,
+  /// with `null` for hidden metadata
+  pub fn associated_data(&self) -> Vec<u8> {
+    let metadata = self.metadata.as_deref().map_or("null", RawValue::get);
+    format!("[{},{}]", self.header.get(), metadata).into_bytes()
+  }
+}
+
+/// Versioned header stored in front of the ciphertext.
+///
+/// Records every parameter needed to re-derive the key, so the defaults
+/// can change without locking older vaults out.
 #[derive(Serialize, Deserialize, Clone)]
+pub struct VaultHeader {
+  /// File format version, see [`VAULT_FORMAT_VERSION`]
+  pub version: u32,
+  /// Key derivation function and its cost parameters.
+  /// Only vaults without key slots (version `2` and older) have one.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub kdf: Option<KdfParams>,
+  /// Random KDF salt, alongside `kdf`
+  /// Must be unique per vault.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub salt: Option<[u8; 16]>,
+  /// Cipher used for the ciphertext
+  pub cipher: CipherKind,
+  /// Hash of the derived key, to tell a wrong password apart from a
+  /// tampered header when decryption fails
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub key_check: Option<[u8; 32]>,
+  /// Whether a key file is needed as well as the master password,
+  /// for vaults without key slots. Left out when `false`, like in the
+  /// version `2` headers written before it existed.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub key_file: bool,
+  /// Copies of the data key, each opened by a different password or key file
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub key_slots: Vec<KeySlot>,
+}
+
+/// The vault data key, encrypted with a key derived from one password
+/// (and key file), so several people can open a vault without sharing a secret.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KeySlot {
+  /// Who or what the slot is for, shown when managing access
+  pub label: String,
+  /// Key derivation function and its cost parameters for this slot
+  pub kdf: KdfParams,
+  /// Random KDF salt, unique per slot
+  pub salt: [u8; 16],
+  /// Whether the slot needs a key file as well as the password
+  #[serde(default)]
+  pub key_file: bool,
+  /// Whether the slot is opened by a recovery key instead of a password
+  #[serde(default)]
+  pub recovery: bool,
+  /// AES-GCM nonce used to wrap the data key
+  pub nonce: [u8; 12],
+  /// Data key encrypted with the slot key
+  pub wrapped_key: Vec<u8>,
+}
+
+/// Key derivation functions understood by `krypt`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KdfAlgorithm {
+  Argon2id,
+}
+
+/// Key derivation function and the cost parameters it was run with.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct KdfParams {
+  pub algorithm: KdfAlgorithm,
+  /// Memory cost in KiB
+  pub memory_kib: u32,
+  /// Number of passes over memory
+  pub iterations: u32,
+  /// Degree of parallelism (lanes)
+  pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+  /// Argon2id with the `argon2` crate defaults, which is also what
+  /// header-less (version `0`) vaults were encrypted with.
+  fn default() -> Self {
+    Self {
+      algorithm: KdfAlgorithm::Argon2id,
+      memory_kib: argon2::Params::DEFAULT_M_COST,
+      iterations: argon2::Params::DEFAULT_T_COST,
+      parallelism: argon2::Params::DEFAULT_P_COST,
+    }
+  }
+}
+
+/// Authenticated ciphers understood by `krypt`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CipherKind {
+  /// AES-256-GCM with a 96-bit random nonce
+  #[default]
+  Aes256Gcm,
+  /// XChaCha20-Poly1305 with a 192-bit random nonce
+  XChaCha20Poly1305,
+}
+
+impl CipherKind {
+  /// Every cipher a vault can be encrypted with
+  pub const ALL: [CipherKind; 2] = [CipherKind::Aes256Gcm, CipherKind::XChaCha20Poly1305];
+
+  /// Name shown to the user
+  pub fn name(&self) -> &'static str {
+    match self {
+      CipherKind::Aes256Gcm => "AES-256-GCM",
+      CipherKind::XChaCha20Poly1305 => "XChaCha20-Poly1305",
+    }
+  }
+}
+
+/// Plaintext password entry containing a service, username, and password.
+/// Wiped on drop, including clones.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Zeroize, ZeroizeOnDrop)]
 pub struct PasswordEntry {
+  /// Stable identity of the entry, nil for entries from before IDs until
+  /// [`PasswordVault::assign_entry_ids`]
+  #[serde(default)]
+  #[zeroize(skip)]
+  pub id: Uuid,
   pub service: String,
   pub username: String,
   pub password: String,
+  /// Websites the login is for, normalized by [`crate::domain::normalize_url`]
+  #[serde(default)]
+  pub urls: Vec<String>,
+  /// When the entry was added
+  #[serde(default)]
+  #[zeroize(skip)]
+  pub created_at: DateTime<Utc>,
+  /// When the entry was last changed
+  #[serde(default)]
+  #[zeroize(skip)]
+  pub modified_at: DateTime<Utc>,
+  /// Passwords this entry had before, oldest first
+  #[serde(default)]
+  pub history: Vec<PasswordHistoryItem>,
+}
+
+/// A password an entry used to have, encrypted with the rest of the vault
+#[derive(Serialize, Deserialize, Clone, PartialEq, Zeroize, ZeroizeOnDrop)]
+pub struct PasswordHistoryItem {
+  pub password: String,
+  /// When the password was replaced
+  #[zeroize(skip)]
+  pub retired_at: DateTime<Utc>,
+}
+
+impl PasswordEntry {
+  /// Entry made just now, with a new ID
+  pub fn new(service: &str, username: &str, password: &str) -> Self {
+    let now = Utc::now();
+    Self {
+      id: Uuid::new_v4(),
+      service: service.to_string(),
+      username: username.to_string(),
+      password: password.to_string(),
+      urls: Vec::new(),
+      created_at: now,
+      modified_at: now,
+      history: Vec::new(),
+    }
+  }
+
+  /// Changes the password, moving the old one into the history.
+  /// Only the newest `max_history` old passwords are kept.
+  pub fn set_password(&mut self, password: &str, max_history: usize) {
+    if self.password == password {
+      return;
+    }
+    let now = Utc::now();
+    let old = std::mem::replace(&mut self.password, password.to_string());
+    self.history.push(PasswordHistoryItem { password: old, retired_at: now });
+    let excess = self.history.len().saturating_sub(max_history);
+    self.history.drain(..excess);
+    self.modified_at = now;
+  }
+
+  /// Makes an old password from the history current again, retiring the
+  /// current one in its place. Does nothing if `index` is out of range.
+  pub fn restore_password(&mut self, index: usize, max_history: usize) {
+    if index >= self.history.len() {
+      return;
+    }
+    let restored = self.history.remove(index);
+    self.set_password(&restored.password, max_history);
+  }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Two entries as saved before entry IDs existed
+  const VAULT_WITHOUT_IDS: &str = r#"{"entries":[
+    {"service":"a","username":"me","password":"1"},
+    {"service":"a","username":"me","password":"2"}
+  ]}"#;
+
+  #[test]
+  fn entries_without_ids_get_new_ones() {
+    let mut vault: PasswordVault = serde_json::from_str(VAULT_WITHOUT_IDS).unwrap();
+    assert!(vault.entries.iter().all(|entry| entry.id.is_nil()));
+    vault.assign_entry_ids(None);
+    assert!(vault.entries.iter().all(|entry| !entry.id.is_nil()));
+    assert_ne!(vault.entries[0].id, vault.entries[1].id);
+  }
+
+  #[test]
+  fn entries_without_ids_match_a_known_copy() {
+    let mut known: PasswordVault = serde_json::from_str(VAULT_WITHOUT_IDS).unwrap();
+    known.assign_entry_ids(None);
+    let mut copy: PasswordVault = serde_json::from_str(VAULT_WITHOUT_IDS).unwrap();
+    copy.assign_entry_ids(Some(&known));
+    // Twins with the same service and username pair up in order
+    assert_eq!(copy.entries[0].id, known.entries[0].id);
+    assert_eq!(copy.entries[1].id, known.entries[1].id);
+    assert!(copy.entries[0] == known.entries[0]);
+  }
+
+  fn history(entry: &PasswordEntry) -> Vec<&str> {
+    entry.history.iter().map(|item| item.password.as_str()).collect()
+  }
+
+  #[test]
+  fn password_history_keeps_the_newest() {
+    let mut entry = PasswordEntry::new("a", "me", "1");
+    entry.set_password("1", 2);
+    assert!(entry.history.is_empty());
+    for password in ["2", "3", "4"] {
+      entry.set_password(password, 2);
+    }
+    assert_eq!(history(&entry), ["2", "3"]);
+
+    entry.restore_password(0, 2);
+    assert_eq!(entry.password, "2");
+    assert_eq!(history(&entry), ["3", "4"]);
+  }
+}
+
@@ -14,6 +14,7 @@ pub struct PasswordGeneratorConfig {
   pub include_numbers: bool,
   pub include_symbols: bool,
   /// Whether or not to use words instead of perfectly random ones
+  #[allow(dead_code)] // TODO word-based generation
   pub use_words: bool,
 }
 
@@ -30,18 +31,11 @@ impl Default for PasswordGeneratorConfig {
   }
 }
 
+#[derive(Default)]
 pub struct PasswordGenerator {
   pub(super) generated_password: String,
   pub(super) config: PasswordGeneratorConfig,
 }
-impl Default for PasswordGenerator {
-  fn default() -> Self {
-    Self {
-      generated_password: String::new(),
-      config: PasswordGeneratorConfig::default(),
-    }
-  }
-}
 
 impl PasswordGenerator {
   pub fn generate(&mut self) -> Option<String> {
@@ -79,6 +73,7 @@ impl PasswordGenerator {
     let estimate = zxcvbn(password, &[]);
     estimate.crack_times().offline_slow_hashing_1e4_per_second().to_string()
   }
+  /// zxcvbn strength score from 0 (weakest) to 4 (strongest)
   pub fn get_password_score(password: &str) -> u8{
     let estimate = zxcvbn(password, &[]);
     estimate.score() as u8
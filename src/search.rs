@@ -1,32 +1,36 @@
 //! The `search` module handles the password entry search functionality
 //! 
 //! # Responsibilities
-//! - Reading all password entries for their service and username
+//! - Reading all password entries for their service, username and websites
 //! - Ranking based on a score function
+use crate::domain;
 use crate::models::{PasswordVault, PasswordEntry};
 
 impl PasswordVault {
   /// Searches `models::PasswordEntry` inside of entries, and 
-  /// returns a vec of the index, entry, and score.
+  /// returns a vec of the entry and score.
   /// If the entry has nothing in common with the query, it is removed.
   /// 
+  /// A query that is a URL or host name, like `https://login.example.com/x`,
+  /// ranks entries with a website on the same site (`example.com`) first.
+  /// 
   /// # Arguments 
-  /// - `query`: The search query to match against the service and username.
+  /// - `query`: The search query to match against the service, username and websites.
   /// 
   /// # Returns
   /// A tuple containing:
-  /// - `index`: the original index of the entry in self.entries
   /// - `entry`: the PasswordEntry object
   /// - `score`: the score of the entry based on the search query
-  pub fn search_entries(&self, query: &str) -> Vec<(usize, &PasswordEntry, u32)> {
-    let query = query.to_lowercase();
+  pub fn search_entries(&self, query: &str) -> Vec<(&PasswordEntry, u32)> {
+    let query_host = domain::host(query);
+    let query_site = query_host.as_deref().map(domain::registrable_domain);
+    let query = query.trim().to_lowercase();
 
     self.entries
       .iter()
-      .enumerate()
-      .filter_map(|(i, entry)| {
+      .filter_map(|entry| {
         if query.is_empty() {
-          return Some((i, entry, 0));
+          return Some((entry, 0));
         }
 
         let service_lower = entry.service.to_lowercase();
@@ -39,8 +43,42 @@ impl PasswordVault {
         if service_lower.contains(&query) { score += 10; }
         if user_lower.contains(&query) { score += 5; }
 
-        if score > 0 { Some((i, entry, score)) } else { None }
+        let hosts = entry.urls.iter().filter_map(|url| domain::host(url));
+        for host in hosts {
+          if query_host.as_ref() == Some(&host) { score += 500; }
+          if query_site.as_ref() == Some(&domain::registrable_domain(&host)) { score += 2000; }
+          if host.contains(&query) { score += 10; }
+        }
+        if query_site.as_ref() == Some(&service_lower) { score += 1000; }
+
+        if score > 0 { Some((entry, score)) } else { None }
       })
       .collect::<Vec<_>>()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(service: &str, url: &str) -> PasswordEntry {
+    let mut entry = PasswordEntry::new(service, "me", "secret");
+    entry.urls.push(url.to_string());
+    entry
+  }
+
+  #[test]
+  fn url_queries_rank_the_same_site_first() {
+    let mut vault = PasswordVault::new("test");
+    vault.entries.push(entry("Other", "https://example.co.uk/"));
+    vault.entries.push(entry("Mail", "https://www.example.com/"));
+    vault.entries.push(entry("Bank", "https://bank.co.il/"));
+
+    let mut results = vault.search_entries("https://login.example.com/x");
+    results.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    assert_eq!(results[0].0.service, "Mail");
+
+    let results = vault.search_entries("https://evil.co.il/login");
+    assert!(results.iter().all(|(entry, _)| entry.service != "Bank"));
+  }
+}
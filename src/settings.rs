@@ -0,0 +1,140 @@
+//! The `settings` module handles persistent application settings.
+//!
+//! Responsibilities:
+//! - Locate the settings file and default vault directory (XDG on Linux).
+//! - Load and save the list of vault directories and registered vault files.
+//! - Hold the auto-lock timeouts, clipboard clearing delay, and password history length.
+//!
+//! Settings never contain secrets, so they are stored as plain JSON.
+use crate::vault::BackupPolicy;
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+/// Directory name used under the platform data and config directories
+const APP_DIR: &str = "pixelvault";
+
+/// Relative vault directory used before vault directories were configurable
+const LEGACY_VAULT_DIR: &str = "vaults";
+
+/// Settings persisted between runs
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+  /// Directories scanned for vaults, the first one is where new vaults go
+  pub vault_dirs: Vec<String>,
+  /// Vault files registered individually, from anywhere on disk
+  pub vault_files: Vec<String>,
+  /// Which timestamped backups are kept on each save
+  pub backup_policy: BackupPolicy,
+  /// When an unlocked vault locks itself
+  pub auto_lock: AutoLockPolicy,
+  /// Seconds before a copied password is cleared from the clipboard, `0` for never
+  pub clipboard_clear_secs: u64,
+  /// Old passwords kept per entry when a password is changed
+  pub max_password_history: usize,
+}
+
+/// When an unlocked vault locks itself, in seconds. `0` turns a rule off.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AutoLockPolicy {
+  /// Lock after this long without keyboard or mouse input
+  pub idle_secs: u64,
+  /// Lock after the window has been minimized or unfocused for this long
+  pub background_secs: u64,
+}
+
+impl Default for AutoLockPolicy {
+  fn default() -> Self {
+    Self {
+      idle_secs: 5 * 60,
+      background_secs: 60,
+    }
+  }
+}
+
+impl Default for Settings {
+  /// The platform data directory, plus the old relative `vaults/` directory
+  /// if it exists so vaults made by older versions stay visible.
+  fn default() -> Self {
+    let mut vault_dirs = vec![default_vault_dir().to_string_lossy().to_string()];
+    if let Ok(legacy) = fs::canonicalize(LEGACY_VAULT_DIR) {
+      let legacy = legacy.to_string_lossy().to_string();
+      if !vault_dirs.contains(&legacy) {
+        vault_dirs.push(legacy);
+      }
+    }
+    Self {
+      vault_dirs,
+      vault_files: Vec::new(),
+      backup_policy: BackupPolicy::default(),
+      auto_lock: AutoLockPolicy::default(),
+      clipboard_clear_secs: 30,
+      max_password_history: 10,
+    }
+  }
+}
+
+/// Default directory for new vaults, e.g. `~/.local/share/pixelvault/vaults`.
+/// Falls back to the relative `vaults/` directory if there is no data directory.
+pub fn default_vault_dir() -> PathBuf {
+  dirs::data_dir()
+    .map(|dir| dir.join(APP_DIR).join("vaults"))
+    .unwrap_or_else(|| PathBuf::from(LEGACY_VAULT_DIR))
+}
+
+/// Location of the settings file, e.g. `~/.config/pixelvault/settings.json`
+pub fn settings_path() -> PathBuf {
+  dirs::config_dir()
+    .unwrap_or_default()
+    .join(APP_DIR)
+    .join("settings.json")
+}
+
+/// Loads the settings file, and returns default if it is missing
+///
+/// # Errors
+/// Returns an error if the file exists but can't be read or parsed.
+pub fn load() -> Result<Settings, String> {
+  let path = settings_path();
+  if !path.exists() {
+    return Ok(Settings::default());
+  }
+  let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+  serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+/// Saves the settings file, creating its directory if needed
+///
+/// # Errors
+/// Returns an error if the file cannot be written to or cannot be JSON serialized
+pub fn save(settings: &Settings) -> Result<(), String> {
+  let path = settings_path();
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+  }
+  let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+  fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// Turns a user-entered path into an absolute one, so it doesn't depend on
+/// the working directory.
+///
+/// # Errors
+/// Returns an error if the path is empty or doesn't exist.
+pub fn absolute_path(path: &str) -> Result<String, String> {
+  let path = path.trim();
+  if path.is_empty() {
+    return Err("Path cannot be empty".into());
+  }
+  let expanded = match path.strip_prefix("~/") {
+    Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+    None => Path::new(path).to_path_buf(),
+  };
+  fs::canonicalize(&expanded)
+    .map(|p| p.to_string_lossy().to_string())
+    .map_err(|e| format!("{}: {}", expanded.display(), e))
+}
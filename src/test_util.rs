@@ -0,0 +1,32 @@
+//! Helpers shared by the unit tests of several modules
+use crate::models::{KdfAlgorithm, KdfParams, PasswordEntry, PasswordVault};
+use std::{fs, path::PathBuf};
+
+/// Cheap KDF costs, the tests don't need a slow key derivation
+pub const TEST_KDF: KdfParams = KdfParams {
+  algorithm: KdfAlgorithm::Argon2id,
+  memory_kib: 64,
+  iterations: 1,
+  parallelism: 1,
+};
+
+/// Fresh, empty temp directory for `test`, which must be unique across
+/// tests since they run in parallel
+pub fn temp_dir(test: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("pixelvault-{}-{}", test, std::process::id()));
+  fs::remove_dir_all(&dir).ok();
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Path of `file` in a fresh [`temp_dir`] for `test`
+pub fn temp_path(test: &str, file: &str) -> String {
+  temp_dir(test).join(file).to_string_lossy().to_string()
+}
+
+/// A small vault with one entry
+pub fn sample_vault() -> PasswordVault {
+  let mut vault = PasswordVault::new("Sample");
+  vault.entries.push(PasswordEntry::new("example.com", "me", "hunter2"));
+  vault
+}
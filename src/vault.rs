@@ -1,73 +1,787 @@
 //! The `vault` module handles vault persistence.
 //!
 //! Responsibilities:
-//! - Load a vault from a file.
-//! - Save a vault to a file.
+//! - Load a vault from a file, falling back to its backup.
+//! - Save a vault to a file, crash-safely.
+//! - Keep rolling timestamped backups of every save.
+//! - Fingerprint vault files to notice changes made elsewhere.
 //! - Delete a vault.
 //! - List all available vaults
-use crate::models::EncryptedVault;
-use std::fs;
+//! - Map vault names to safe, unique file names
+use crate::{
+  error::VaultError,
+  lock,
+  models::{CipherKind, EncryptedVault, KdfParams, SealedHeader, VaultHeader, VaultMetadata},
+};
+use chrono::{DateTime, Days, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::{
+  collections::HashSet,
+  fs::{self, File},
+  hash::{DefaultHasher, Hash, Hasher},
+  io::Write,
+  path::{Path, PathBuf},
+};
+use unicode_normalization::UnicodeNormalization;
+
+/// Format of backup file stems, always in UTC
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
 
 /// Reads the README and places it into a string
 const README: &str = include_str!("../README.md");
 
+/// Original header-less vault file, before [`VaultHeader`] existed.
+#[derive(Deserialize)]
+struct LegacyEncryptedVault {
+  salt: [u8; 16],
+  nonce: [u8; 12],
+  ciphertext: Vec<u8>,
+}
+
+impl From<LegacyEncryptedVault> for EncryptedVault {
+  /// Legacy vaults were always Argon2id (crate defaults) with AES-256-GCM.
+  fn from(legacy: LegacyEncryptedVault) -> Self {
+    EncryptedVault {
+      header: VaultHeader {
+        version: 0,
+        kdf: Some(KdfParams::default()),
+        salt: Some(legacy.salt),
+        cipher: CipherKind::Aes256Gcm,
+        key_check: None,
+        key_file: false,
+        key_slots: Vec::new(),
+      },
+      metadata: None,
+      nonce: legacy.nonce.to_vec(),
+      ciphertext: legacy.ciphertext,
+      sealed_header: None,
+    }
+  }
+}
+
+/// Magic bytes at the start of a binary vault container
+const VAULT_MAGIC: &[u8; 8] = b"PXVAULT\0";
+
+/// Extension of vault files made by this version
+pub const VAULT_EXTENSION: &str = "pvault";
+
+/// Extensions picked up when scanning directories, `json` being the old text format
+const VAULT_EXTENSIONS: [&str; 2] = [VAULT_EXTENSION, "json"];
+
+/// Everything in a binary container except the ciphertext.
+/// Header and metadata stay raw JSON, see [`SealedHeader`].
+#[derive(Serialize, Deserialize)]
+struct ContainerHeader {
+  header: Box<RawValue>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  metadata: Option<Box<RawValue>>,
+  nonce: Vec<u8>,
+  /// Length of the ciphertext following the header, so a cut off file is
+  /// told apart from a tampered one. Missing in containers from before it.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  ciphertext_len: Option<u64>,
+}
+
+/// Any vault file layout that can be read from disk
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredVault {
+  Versioned(EncryptedVault),
+  Legacy(LegacyEncryptedVault),
+}
+
+/// Serializes a vault into the binary container:
+/// 
+/// ```text
+/// "PXVAULT\0" | header length (u32 LE) | header (JSON) | ciphertext (raw bytes)
+/// ```
+/// 
+/// Only the small header is JSON, the ciphertext is stored as is.
+/// The header and metadata are written exactly as they were sealed,
+/// along with the ciphertext length.
+/// 
+/// # Errors
+/// Returns an error if the header can't be serialized.
+pub fn encode(vault: &EncryptedVault) -> Result<Vec<u8>, VaultError> {
+  let malformed = |e: serde_json::Error| VaultError::Malformed(e.to_string());
+  let sealed = vault.sealed_header().map_err(malformed)?;
+  let header = serde_json::to_vec(&ContainerHeader {
+    header: sealed.header,
+    metadata: sealed.metadata,
+    nonce: vault.nonce.clone(),
+    ciphertext_len: Some(vault.ciphertext.len() as u64),
+  })
+  .map_err(malformed)?;
+  let header_len = u32::try_from(header.len())
+    .map_err(|_| VaultError::Malformed("header too large".into()))?;
+
+  let mut data = Vec::with_capacity(VAULT_MAGIC.len() + 4 + header.len() + vault.ciphertext.len());
+  data.extend_from_slice(VAULT_MAGIC);
+  data.extend_from_slice(&header_len.to_le_bytes());
+  data.extend_from_slice(&header);
+  data.extend_from_slice(&vault.ciphertext);
+  Ok(data)
+}
+
+/// Parses a vault file, either a binary container (see [`encode`])
+/// or one of the older JSON layouts, told apart by the magic bytes.
+/// 
+/// # Errors
+/// Returns an error if the data is truncated, has bytes appended, or is not a vault.
+pub fn decode(data: &[u8]) -> Result<EncryptedVault, VaultError> {
+  let malformed = |e: serde_json::Error| VaultError::Malformed(e.to_string());
+  let Some(rest) = data.strip_prefix(VAULT_MAGIC) else {
+    let vault = match serde_json::from_slice(data).map_err(malformed)? {
+      StoredVault::Versioned(vault) => vault,
+      StoredVault::Legacy(legacy) => legacy.into(),
+    };
+    return Ok(vault);
+  };
+  let truncated = || VaultError::Malformed("file is truncated".into());
+  let (len, rest) = rest.split_first_chunk::<4>().ok_or_else(truncated)?;
+  let len = u32::from_le_bytes(*len) as usize;
+  if rest.len() < len {
+    return Err(truncated());
+  }
+  let (header, ciphertext) = rest.split_at(len);
+  let container: ContainerHeader = serde_json::from_slice(header).map_err(malformed)?;
+  match container.ciphertext_len {
+    Some(len) if len < ciphertext.len() as u64 => {
+      return Err(VaultError::Malformed("file has unexpected data at the end".into()));
+    }
+    Some(len) if len > ciphertext.len() as u64 => return Err(truncated()),
+    _ => {}
+  }
+  let metadata = match &container.metadata {
+    Some(metadata) => Some(serde_json::from_str(metadata.get()).map_err(malformed)?),
+    None => None,
+  };
+  Ok(EncryptedVault {
+    header: serde_json::from_str(container.header.get()).map_err(malformed)?,
+    metadata,
+    nonce: container.nonce,
+    ciphertext: ciphertext.to_vec(),
+    sealed_header: Some(SealedHeader {
+      header: container.header,
+      metadata: container.metadata,
+    }),
+  })
+}
+
 /// Load the encrypted vault from disk using filesystem
 /// 
+/// Header-less vaults are given a version `0` header, and JSON vaults are
+/// moved to the binary container, both the next time they are saved.
+/// 
 /// # Arguments
 /// - `path`: path of the wanted file
 /// 
 /// # Returns
-/// An [`EncryptedVault`] with the salt, nonce, and encrypted vault data.
+/// An [`EncryptedVault`] with the header, nonce, and encrypted vault data.
 /// 
 /// # Errors
-/// Returns a string error if the file does not exist or is not a valid JSON file.
-pub fn load(path: &str) -> Result<EncryptedVault, String> {
-  let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
-  let vault = serde_json::from_str(&data).map_err(|e| e.to_string())?;
-  Ok(vault)
+/// Returns [`VaultError::Io`] if the file can't be read,
+/// or [`VaultError::Malformed`] if it is not a vault.
+pub fn load(path: &str) -> Result<EncryptedVault, VaultError> {
+  let data = fs::read(path)?;
+  decode(&data)
+}
+
+/// Which file [`load_with_backup`] ended up reading
+#[derive(PartialEq, Eq, Debug)]
+pub enum LoadSource {
+  /// The vault file itself, with the fingerprint of the bytes read
+  Primary(FileFingerprint),
+  /// `<path>.bak`, because the vault file was missing or corrupt
+  Backup,
+}
+
+/// Path of the backup kept next to a vault, `name.pvault.bak`
+pub fn backup_path(path: &str) -> String {
+  format!("{}.bak", path)
+}
+
+/// Load a vault, falling back to its backup if the vault file is unreadable
+/// 
+/// # Returns
+/// The [`EncryptedVault`] and which file it was read from.
+/// 
+/// # Errors
+/// Returns the error of the vault file if neither file can be loaded.
+pub fn load_with_backup(path: &str) -> Result<(EncryptedVault, LoadSource), VaultError> {
+  let primary = read_with_fingerprint(path)
+    .and_then(|(data, fingerprint)| Ok((decode(&data)?, fingerprint)));
+  match primary {
+    Ok((vault, fingerprint)) => Ok((vault, LoadSource::Primary(fingerprint))),
+    Err(e) => load(&backup_path(path))
+      .map(|vault| (vault, LoadSource::Backup))
+      .map_err(|_| e),
+  }
 }
 
 /// Save a given encrypted vault at a path
 /// 
+/// Crash-safe write:
+/// 1. The vault is written and fsynced to `<path>.tmp`.
+/// 2. The current vault, if it is readable, is copied to `<path>.bak`.
+/// 3. The temporary file is renamed over `path` and the directory is fsynced.
+/// 4. A timestamped backup is added and old ones are pruned, see [`BackupPolicy`].
+/// 
+/// The file at `path` is therefore always either the old or the new vault.
+/// Once it is the new one, the save counts as done: a failure in step 4
+/// is reported in [`SavedVault::backup_error`] instead of as an error.
+/// It is always written as a binary container, even if it was JSON before,
+/// but keeps its path so locks and registered vault files stay valid.
+/// 
 /// # Arguments
 /// - `path`: the path at which the data will be saved
 /// - `vault`: `EncryptedVault` to be serialized and saved
+/// - `policy`: which timestamped backups to keep
+/// 
+/// # Returns
+/// The fingerprint of the bytes written, and any timestamped backup failure.
+/// 
+/// # Errors
+/// Returns an error if the file cannot be written to or cannot be serialized,
+/// in which case the file at `path` is unchanged.
+pub fn save(
+  path: &str,
+  vault: &EncryptedVault,
+  policy: &BackupPolicy,
+) -> Result<SavedVault, VaultError> {
+  let data = encode(vault)?;
+  let tmp_path = format!("{}.tmp", path);
+  write_synced(&tmp_path, &data).inspect_err(|_| {
+    fs::remove_file(&tmp_path).ok();
+  })?;
+
+  // Never replace a good backup with a corrupt vault
+  if load(path).is_ok() {
+    let backup = fs::read(path)?;
+    write_synced(&backup_path(path), &backup)?;
+  }
+
+  fs::rename(&tmp_path, path).inspect_err(|_| {
+    fs::remove_file(&tmp_path).ok();
+  })?;
+  let fingerprint = FileFingerprint::of(&data);
+  // The new vault is in place now, so nothing below may fail the save
+  let synced = sync_parent_dir(path);
+  let backed_up = synced.and_then(|_| add_backup(path, &data));
+  Ok(SavedVault {
+    fingerprint,
+    backup_error: backed_up.and_then(|_| prune_backups(path, policy)).err(),
+  })
+}
+
+/// A vault [`save`] that replaced the file on disk
+pub struct SavedVault {
+  /// Fingerprint of the bytes written, to tell later writes from elsewhere apart
+  pub fingerprint: FileFingerprint,
+  /// Why the timestamped backups couldn't be updated, the vault itself was saved
+  pub backup_error: Option<VaultError>,
+}
+
+/// Writes `data` to `path` and flushes it to disk before returning
+fn write_synced(path: &str, data: &[u8]) -> Result<(), VaultError> {
+  let mut file = File::create(path)?;
+  file.write_all(data)?;
+  Ok(file.sync_all()?)
+}
+
+/// Flushes a rename in the parent directory of `path` to disk.
+/// Directories can't be opened for syncing on Windows, so this is Unix only.
+fn sync_parent_dir(path: &str) -> Result<(), VaultError> {
+  if cfg!(unix) {
+    let parent = match Path::new(path).parent() {
+      Some(p) if !p.as_os_str().is_empty() => p,
+      _ => Path::new("."),
+    };
+    File::open(parent).and_then(|dir| dir.sync_all())?;
+  }
+  Ok(())
+}
+
+/// Identifies the exact contents of a vault file, as read or written by us
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FileFingerprint {
+  hash: u64,
+}
+
+impl FileFingerprint {
+  /// Fingerprint of the given file contents
+  pub fn of(data: &[u8]) -> Self {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    Self { hash: hasher.finish() }
+  }
+}
+
+/// Reads the vault file at `path`, along with the fingerprint of exactly
+/// the bytes read
 /// 
 /// # Errors
-/// Returns an error if the file cannot be written to or cannot be JSON serialized
-pub fn save(path: &str, vault: &EncryptedVault) -> Result<(), String> {
-  let json = serde_json::to_string_pretty(vault).map_err(|e| e.to_string())?;
-  fs::write(path, json).map_err(|e| e.to_string())
+/// Returns an error if the file can't be read.
+pub fn read_with_fingerprint(path: &str) -> Result<(Vec<u8>, FileFingerprint), VaultError> {
+  let data = fs::read(path)?;
+  let fingerprint = FileFingerprint::of(&data);
+  Ok((data, fingerprint))
 }
 
-/// Gets all vaults in the `vaults/` directory, and returns default if failed
+/// Copies a vault file that can't be read next to it before it gets
+/// overwritten, as `name.pvault.unreadable-<timestamp>`
 /// 
 /// # Returns
-/// - `vault_list`: A vec containing all relative vault filepaths
+/// The path of the copy.
+/// 
+/// # Errors
+/// Returns an error if the copy can't be written.
+pub fn preserve_unreadable(path: &str) -> Result<String, VaultError> {
+  let copy = format!("{}.unreadable-{}", path, Utc::now().format(BACKUP_TIMESTAMP_FORMAT));
+  fs::copy(path, &copy)?;
+  Ok(copy)
+}
+
+/// How many timestamped backups [`save`] keeps per vault.
+/// A backup is kept if either rule wants it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BackupPolicy {
+  /// Keep this many of the most recent saves
+  pub keep_recent: usize,
+  /// Keep the newest save of each of the last this many days
+  pub keep_daily_days: u64,
+}
+
+impl Default for BackupPolicy {
+  fn default() -> Self {
+    Self {
+      keep_recent: 10,
+      keep_daily_days: 7,
+    }
+  }
+}
+
+/// A timestamped backup of a vault
+#[derive(Clone)]
+pub struct BackupInfo {
+  /// Path of the backup file
+  pub path: String,
+  /// When the backed up version was saved
+  pub created: DateTime<Utc>,
+}
+
+/// Directory holding the timestamped backups of a vault,
+/// `<vault dir>/.backups/<vault file name>/`.
+/// Keyed by the whole file name, so `work.json` and `work.pvault` in the
+/// same directory keep their backups apart.
+pub fn backups_dir(path: &str) -> PathBuf {
+  let path = Path::new(path);
+  let file_name = path.file_name().unwrap_or_default();
+  path
+    .parent()
+    .unwrap_or(Path::new(""))
+    .join(".backups")
+    .join(file_name)
+}
+
+/// Lists the timestamped backups of a vault, newest first.
 /// 
 /// # Errors
-/// If the read_dir operation fails, it returns an empty vec.
-pub fn list_vaults() -> Vec<String> {
-  fs::read_dir("vaults")
+/// If the backup directory can't be read, it returns an empty vec.
+pub fn list_backups(path: &str) -> Vec<BackupInfo> {
+  let mut backups: Vec<BackupInfo> = fs::read_dir(backups_dir(path))
     .map(|entries| {
       entries
         .flatten()
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("json"))
-        .map(|e| e.path().to_string_lossy().to_string())
+        .filter_map(|e| {
+          let path = e.path();
+          let stem = path.file_stem()?.to_str()?;
+          let created = NaiveDateTime::parse_from_str(stem, BACKUP_TIMESTAMP_FORMAT).ok()?;
+          Some(BackupInfo {
+            path: path.to_string_lossy().to_string(),
+            created: created.and_utc(),
+          })
+        })
         .collect()
     })
-    .unwrap_or_default()
+    .unwrap_or_default();
+  backups.sort_by_key(|b| std::cmp::Reverse(b.created));
+  backups
+}
+
+/// Writes a new timestamped backup containing `data`
+fn add_backup(path: &str, data: &[u8]) -> Result<(), VaultError> {
+  let dir = backups_dir(path);
+  fs::create_dir_all(&dir)?;
+  let name = format!("{}.{}", Utc::now().format(BACKUP_TIMESTAMP_FORMAT), VAULT_EXTENSION);
+  write_synced(&dir.join(name).to_string_lossy(), data)
+}
+
+/// Deletes the timestamped backups of a vault that `policy` doesn't keep
+fn prune_backups(path: &str, policy: &BackupPolicy) -> Result<(), VaultError> {
+  let backups = list_backups(path);
+  let oldest_day = Utc::now()
+    .date_naive()
+    .checked_sub_days(Days::new(policy.keep_daily_days.saturating_sub(1)))
+    .unwrap_or_default();
+  let mut kept_days = HashSet::new();
+
+  for (i, backup) in backups.iter().enumerate() {
+    let day = backup.created.date_naive();
+    // Backups are newest first, so the first one seen for a day is its newest
+    let keep_daily = policy.keep_daily_days > 0 && day >= oldest_day && kept_days.insert(day);
+    if i >= policy.keep_recent && !keep_daily {
+      fs::remove_file(&backup.path)?;
+    }
+  }
+  Ok(())
 }
 
-/// Deletes the vault at a given filepath.
+/// Gets all vaults in the given directories plus the registered vault files
+/// 
+/// # Arguments
+/// - `dirs`: directories scanned for `.pvault` and `.json` vaults (not recursive)
+/// - `files`: individually registered vault files, kept if they exist
+/// 
+/// # Returns
+/// - `vault_list`: A vec containing all vault filepaths, without duplicates
+/// 
+/// # Errors
+/// Directories that can't be read are skipped.
+pub fn list_vaults(dirs: &[String], files: &[String]) -> Vec<String> {
+  let mut vaults: Vec<String> = dirs
+    .iter()
+    .flat_map(|dir| {
+      fs::read_dir(dir)
+        .map(|entries| {
+          entries
+            .flatten()
+            .filter(|e| {
+              let path = e.path();
+              let extension = path.extension().and_then(|s| s.to_str());
+              extension.is_some_and(|ext| VAULT_EXTENSIONS.contains(&ext))
+            })
+            .map(|e| e.path().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+        })
+        .unwrap_or_default()
+    })
+    .chain(files.iter().filter(|f| Path::new(f).is_file()).cloned())
+    .collect();
+  vaults.sort();
+  vaults.dedup();
+  vaults
+}
+
+/// Longest vault name accepted, in characters
+pub const MAX_VAULT_NAME_LEN: usize = 64;
+
+/// File names Windows reserves for devices, in any case and with any extension
+const RESERVED_FILE_NAMES: [&str; 22] = [
+  "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+  "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Why a vault name can't be used
+#[derive(Debug, PartialEq, Eq)]
+pub enum VaultNameError {
+  Empty,
+  /// Longer than [`MAX_VAULT_NAME_LEN`] characters
+  TooLong,
+  /// Path separator or control character
+  InvalidCharacter(char),
+  /// Maps to a file name the OS reserves
+  Reserved(String),
+}
+
+impl std::fmt::Display for VaultNameError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      VaultNameError::Empty => write!(f, "Vault name cannot be empty"),
+      VaultNameError::TooLong => {
+        write!(f, "Vault name can be at most {} characters", MAX_VAULT_NAME_LEN)
+      }
+      VaultNameError::InvalidCharacter(c) if c.is_control() => {
+        write!(f, "Vault name cannot contain control characters")
+      }
+      VaultNameError::InvalidCharacter(c) => write!(f, "Vault name cannot contain '{}'", c),
+      VaultNameError::Reserved(name) => write!(f, "\"{}\" is a reserved name", name),
+    }
+  }
+}
+
+/// Checks a vault display name, which may be any Unicode text
+/// except path separators and control characters.
+/// The length is counted after NFC normalization.
+///
+/// # Errors
+/// Returns the first problem found with the name.
+pub fn validate_name(name: &str) -> Result<(), VaultNameError> {
+  let name: String = name.trim().nfc().collect();
+  if name.is_empty() {
+    return Err(VaultNameError::Empty);
+  }
+  if name.chars().count() > MAX_VAULT_NAME_LEN {
+    return Err(VaultNameError::TooLong);
+  }
+  if let Some(c) = name.chars().find(|&c| c == '/' || c == '\\' || c.is_control()) {
+    return Err(VaultNameError::InvalidCharacter(c));
+  }
+  Ok(())
+}
+
+/// Maps a display name to a file stem that is safe on every platform:
+/// lowercase letters and digits (any script), with runs of anything else
+/// collapsed to `-`. Names with no letters or digits map to `vault`.
+/// The name is NFC normalized first, so a letter typed with a combining
+/// accent maps the same as the precomposed one.
+///
+/// # Errors
+/// Returns an error if the name is invalid or its stem is reserved.
+pub fn file_stem_for(name: &str) -> Result<String, VaultNameError> {
+  validate_name(name)?;
+  let mut stem = String::new();
+  for c in name.trim().nfc() {
+    if c.is_alphanumeric() {
+      stem.extend(c.to_lowercase());
+    } else if !stem.is_empty() && !stem.ends_with('-') {
+      stem.push('-');
+    }
+  }
+  let stem = stem.trim_end_matches('-');
+  if stem.is_empty() {
+    return Ok("vault".to_string());
+  }
+  if RESERVED_FILE_NAMES.contains(&stem) {
+    return Err(VaultNameError::Reserved(name.trim().to_string()));
+  }
+  Ok(stem.to_string())
+}
+
+/// Path for a new vault named `name` in `dir`, numbered (`name-2.pvault`, ...)
+/// if the file name is already taken.
+///
+/// # Errors
+/// Returns an error if the name can't be mapped to a file name.
+pub fn new_vault_path(dir: &Path, name: &str) -> Result<PathBuf, VaultNameError> {
+  let stem = file_stem_for(name)?;
+  let mut path = dir.join(format!("{}.{}", stem, VAULT_EXTENSION));
+  let mut n = 2;
+  while path.exists() || Path::new(&lock::lock_path(&path.to_string_lossy())).exists() {
+    path = dir.join(format!("{}-{}.{}", stem, n, VAULT_EXTENSION));
+    n += 1;
+  }
+  Ok(path)
+}
+
+/// Unencrypted metadata of a vault file, if it has any.
+/// Vaults made before metadata, or keeping it encrypted, have none.
+pub fn read_metadata(path: &str) -> Option<VaultMetadata> {
+  load(path).ok()?.metadata
+}
+
+/// File name of a vault without the extension, shown when it has no metadata
+pub fn display_name(path: &str) -> String {
+  Path::new(path)
+    .file_stem()
+    .map(|s| s.to_string_lossy().to_string())
+    .unwrap_or_else(|| path.to_string())
+}
+
+/// Deletes the vault at a given filepath, along with its backups.
 /// 
 /// # Errors
 /// Returns an error if the filesystem remove fails
-pub fn delete(path: &String) -> Result<(), String> {
-  fs::remove_file(path).map_err(|e| e.to_string())
+pub fn delete(path: &String) -> Result<(), VaultError> {
+  fs::remove_file(path)?;
+  fs::remove_file(backup_path(path)).ok();
+  fs::remove_dir_all(backups_dir(path)).ok();
+  Ok(())
 }
 
 /// Returns the `README.md` as a string
 pub fn get_readme() -> String {
   README.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    krypt,
+    test_util::{TEST_KDF, sample_vault, temp_dir, temp_path},
+  };
+
+  fn encrypted_sample(password: &str) -> EncryptedVault {
+    let key = krypt::new_session_key(password, &TEST_KDF, None).unwrap();
+    krypt::encrypt_vault(&sample_vault(), &key).unwrap()
+  }
+
+  #[test]
+  fn container_round_trip() {
+    let encrypted = encrypted_sample("pw");
+    let data = encode(&encrypted).unwrap();
+    assert!(data.starts_with(VAULT_MAGIC));
+
+    let decoded = decode(&data).unwrap();
+    assert_eq!(decoded.ciphertext, encrypted.ciphertext);
+    assert_eq!(decoded.nonce, encrypted.nonce);
+    assert_eq!(decoded.metadata.unwrap().name, "Sample");
+    assert!(krypt::unlock_vault(&decode(&data).unwrap(), "pw", None).is_ok());
+  }
+
+  #[test]
+  fn container_is_smaller_than_json() {
+    let encrypted = encrypted_sample("pw");
+    let json = serde_json::to_vec(&encrypted).unwrap();
+    let binary = encode(&encrypted).unwrap();
+    assert!(binary.len() < json.len());
+    // The JSON layout still loads
+    assert_eq!(decode(&json).unwrap().ciphertext, encrypted.ciphertext);
+  }
+
+  #[test]
+  fn truncated_container_is_malformed() {
+    let data = encode(&encrypted_sample("pw")).unwrap();
+    assert!(matches!(decode(&data[..VAULT_MAGIC.len() + 2]), Err(VaultError::Malformed(_))));
+    assert!(matches!(decode(&data[..VAULT_MAGIC.len() + 20]), Err(VaultError::Malformed(_))));
+    assert!(matches!(decode(b"not a vault"), Err(VaultError::Malformed(_))));
+  }
+
+  #[test]
+  fn truncated_ciphertext_is_malformed() {
+    let data = encode(&encrypted_sample("pw")).unwrap();
+    assert!(matches!(decode(&data[..data.len() - 5]), Err(VaultError::Malformed(_))));
+    let mut longer = data.clone();
+    longer.push(0);
+    assert!(matches!(decode(&longer), Err(VaultError::Malformed(_))));
+
+    // Falls back to the .bak instead of blaming the header or password
+    let path = temp_path("truncated", "vault.pvault");
+    let policy = BackupPolicy::default();
+    save(&path, &encrypted_sample("pw"), &policy).unwrap();
+    save(&path, &encrypted_sample("pw"), &policy).unwrap();
+    let saved = fs::read(&path).unwrap();
+    fs::write(&path, &saved[..saved.len() - 5]).unwrap();
+    let (vault, source) = load_with_backup(&path).unwrap();
+    assert_eq!(source, LoadSource::Backup);
+    assert!(krypt::unlock_vault(&vault, "pw", None).is_ok());
+  }
+
+  #[test]
+  fn legacy_json_vault_loads_as_version_0() {
+    let legacy = serde_json::json!({
+      "salt": vec![7u8; 16],
+      "nonce": vec![1u8; 12],
+      "ciphertext": [1, 2, 3],
+    });
+    let vault = decode(legacy.to_string().as_bytes()).unwrap();
+    assert_eq!(vault.header.version, 0);
+    assert_eq!(vault.header.salt, Some([7u8; 16]));
+    assert_eq!(vault.nonce, vec![1u8; 12]);
+  }
+
+  #[test]
+  fn corrupt_vault_falls_back_to_bak() {
+    let path = temp_path("bak", "vault.pvault");
+    let policy = BackupPolicy::default();
+    save(&path, &encrypted_sample("pw"), &policy).unwrap();
+    assert!(!fs::exists(backup_path(&path)).unwrap());
+    save(&path, &encrypted_sample("pw"), &policy).unwrap();
+    assert!(fs::exists(backup_path(&path)).unwrap());
+
+    fs::write(&path, b"garbage").unwrap();
+    let (_, source) = load_with_backup(&path).unwrap();
+    assert_eq!(source, LoadSource::Backup);
+
+    // Saving over the corrupt file keeps the good .bak
+    save(&path, &encrypted_sample("pw"), &policy).unwrap();
+    assert!(load(&backup_path(&path)).is_ok());
+  }
+
+  #[test]
+  fn save_fingerprints_the_bytes_written() {
+    let path = temp_path("fingerprint", "vault.pvault");
+    let written = save(&path, &encrypted_sample("pw"), &BackupPolicy::default())
+      .unwrap()
+      .fingerprint;
+    let (_, read) = read_with_fingerprint(&path).unwrap();
+    assert_eq!(written, read);
+    let (_, source) = load_with_backup(&path).unwrap();
+    assert_eq!(source, LoadSource::Primary(written));
+  }
+
+  #[test]
+  fn backups_are_pruned_and_kept_per_file() {
+    let path = temp_path("backups", "work.pvault");
+    let other = format!("{}.json", path.trim_end_matches(".pvault"));
+    let policy = BackupPolicy { keep_recent: 3, keep_daily_days: 0 };
+    for _ in 0..5 {
+      save(&path, &encrypted_sample("pw"), &policy).unwrap();
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    save(&other, &encrypted_sample("pw"), &policy).unwrap();
+
+    assert_eq!(list_backups(&path).len(), 3);
+    assert_eq!(list_backups(&other).len(), 1);
+  }
+
+  #[test]
+  fn backup_failure_still_saves_the_vault() {
+    let path = temp_path("backup-failure", "vault.pvault");
+    let policy = BackupPolicy::default();
+    // A plain file where the backups directory should be
+    fs::write(backups_dir(&path).parent().unwrap(), b"").unwrap();
+    save(&path, &encrypted_sample("old"), &policy).unwrap();
+
+    let saved = save(&path, &encrypted_sample("new"), &policy).unwrap();
+    assert!(saved.backup_error.is_some());
+    let (on_disk, source) = load_with_backup(&path).unwrap();
+    assert_eq!(source, LoadSource::Primary(saved.fingerprint));
+    assert!(krypt::unlock_vault(&on_disk, "new", None).is_ok());
+  }
+
+  #[test]
+  fn names_with_path_separators_are_rejected() {
+    assert_eq!(validate_name("../../etc/passwd"), Err(VaultNameError::InvalidCharacter('/')));
+    assert_eq!(validate_name("..\\secrets"), Err(VaultNameError::InvalidCharacter('\\')));
+    assert_eq!(validate_name("a\nb"), Err(VaultNameError::InvalidCharacter('\n')));
+    assert_eq!(validate_name("   "), Err(VaultNameError::Empty));
+    assert_eq!(validate_name(&"a".repeat(MAX_VAULT_NAME_LEN + 1)), Err(VaultNameError::TooLong));
+  }
+
+  #[test]
+  fn dot_and_punctuation_names_map_to_vault() {
+    assert_eq!(file_stem_for("..").unwrap(), "vault");
+    assert_eq!(file_stem_for(".").unwrap(), "vault");
+    assert_eq!(file_stem_for("!?*:<>").unwrap(), "vault");
+  }
+
+  #[test]
+  fn reserved_device_names_are_rejected() {
+    assert_eq!(file_stem_for("CON"), Err(VaultNameError::Reserved("CON".into())));
+    assert_eq!(file_stem_for(" lpt1 "), Err(VaultNameError::Reserved("lpt1".into())));
+    // The dot becomes a dash, so the file isn't `con.pvault`
+    assert_eq!(file_stem_for("con.txt").unwrap(), "con-txt");
+  }
+
+  #[test]
+  fn non_ascii_names_keep_their_letters() {
+    assert_eq!(file_stem_for("Café Ünïcode").unwrap(), "café-ünïcode");
+    assert_eq!(file_stem_for("Пароли").unwrap(), "пароли");
+    assert_eq!(file_stem_for("n\u{303}ame").unwrap(), "\u{f1}ame");
+    assert_eq!(file_stem_for("n\u{303}ame"), file_stem_for("\u{f1}ame"));
+  }
+
+  #[test]
+  fn taken_file_names_are_numbered() {
+    let dir = temp_dir("names");
+    let first = new_vault_path(&dir, "My Vault").unwrap();
+    assert_eq!(first, dir.join("my-vault.pvault"));
+    fs::write(&first, b"").unwrap();
+    assert_eq!(new_vault_path(&dir, "my vault").unwrap(), dir.join("my-vault-2.pvault"));
+    fs::write(dir.join("my-vault-2.pvault"), b"").unwrap();
+    assert_eq!(new_vault_path(&dir, "MY_VAULT").unwrap(), dir.join("my-vault-3.pvault"));
+  }
+}